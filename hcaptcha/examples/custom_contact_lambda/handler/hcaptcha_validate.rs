@@ -1,17 +1,33 @@
 use super::error::ContactError;
-use super::param;
-use hcaptcha::{HcaptchaCaptcha, HcaptchaClient, HcaptchaRequest, HcaptchaResponse};
+use super::param::ParameterStore;
+use hcaptcha::{CachedSecretProvider, HcaptchaCaptcha, HcaptchaClient, HcaptchaResponse};
+use std::sync::OnceLock;
+use std::time::Duration;
 
 const HCAPTCHA_SECRET: &str = "/hcaptcha/secret";
+const SECRET_TTL: Duration = Duration::from_secs(300);
 
-pub async fn response_valid(captcha: HcaptchaCaptcha) -> Result<HcaptchaResponse, ContactError> {
-    let secret = param::get_parameter(HCAPTCHA_SECRET).await?;
-
-    let client = HcaptchaClient::new();
+/// Client kept between invocations of a warm lambda so the secret is only
+/// read from the parameter store when the cached value expires.
+static CLIENT: OnceLock<HcaptchaClient> = OnceLock::new();
 
-    let request = HcaptchaRequest::new(&secret, captcha)?;
+fn client() -> Result<&'static HcaptchaClient, ContactError> {
+    if let Some(client) = CLIENT.get() {
+        return Ok(client);
+    }
+    let client = HcaptchaClient::builder()
+        .secret_provider(CachedSecretProvider::new(
+            ParameterStore {
+                key: HCAPTCHA_SECRET,
+            },
+            SECRET_TTL,
+        ))
+        .build()?;
+    Ok(CLIENT.get_or_init(|| client))
+}
 
-    let res = client.verify_client_response(request).await?;
+pub async fn response_valid(captcha: HcaptchaCaptcha) -> Result<HcaptchaResponse, ContactError> {
+    let res = client()?.verify_captcha(captcha).await?;
 
     Ok(res)
 }
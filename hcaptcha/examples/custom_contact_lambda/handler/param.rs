@@ -1,4 +1,5 @@
 use super::error::ContactError;
+use hcaptcha::{async_trait, HcaptchaError, SecretProvider, Secrets};
 use tracing::instrument;
 
 #[instrument(name = "get the secret key from parameter store")]
@@ -6,3 +7,19 @@ pub async fn get_parameter(key: &str) -> Result<String, ContactError> {
     // Extract the secret key from your parameter store
     Ok("0x123456789abcedf0123456789abcedf012345678".to_owned())
 }
+
+/// Provide the hcaptcha secret from the parameter store.
+#[derive(Debug)]
+pub struct ParameterStore {
+    pub key: &'static str,
+}
+
+#[async_trait]
+impl SecretProvider for ParameterStore {
+    async fn secrets(&self) -> Result<Secrets, HcaptchaError> {
+        let secret = get_parameter(self.key)
+            .await
+            .map_err(|e| HcaptchaError::SecretProvider(Box::new(e)))?;
+        Secrets::new(&secret)
+    }
+}
@@ -0,0 +1,626 @@
+//! Actix-web extractor and middleware verifying the hcaptcha token.
+//!
+//! The [VerifiedHcaptcha] extractor deserializes a form or JSON body into a
+//! struct deriving [Hcaptcha] and verifies it with the [HcaptchaConfig]
+//! registered as app data. The [HcaptchaMiddleware] guards a scope, reading
+//! the token from a header, query or body field.
+//!
+//! Both use the peer address of the connection as the remoteip, or the
+//! address of the client reported by a proxy in the [TrustedProxies]. A
+//! failed verification is returned as the [HcaptchaError], which implements
+//! [ResponseError](actix_web::ResponseError).
+//!
+//! # Example
+//! ```no_run
+//!     use actix_web::{web, App, HttpServer};
+//!     use hcaptcha::actix::{HcaptchaConfig, HcaptchaMiddleware, VerifiedHcaptcha};
+//!     use hcaptcha::{Hcaptcha, HcaptchaClient};
+//!
+//!     #[derive(Hcaptcha, serde::Deserialize)]
+//!     struct ContactForm {
+//!         name: String,
+//!         #[captcha]
+//!         #[serde(rename = "h-captcha-response")]
+//!         token: String,
+//!     }
+//!
+//!     async fn contact(form: VerifiedHcaptcha<ContactForm>) -> String {
+//!         format!("{}, thank you for your contact request.", form.payload.name)
+//!     }
+//!
+//!     async fn comment() -> &'static str {
+//!         "Comment received"
+//!     }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> std::io::Result<()> {
+//!     let secret = "0x123456789abcde0f123456789abcdef012345678";
+//!     let client = HcaptchaClient::new();
+//!
+//!     HttpServer::new(move || {
+//!         App::new()
+//!             .app_data(HcaptchaConfig::new(client.clone(), secret))
+//!             .route("/contact", web::post().to(contact))
+//!             .service(
+//!                 web::scope("/comments")
+//!                     .wrap(HcaptchaMiddleware::new(client.clone(), secret))
+//!                     .route("", web::post().to(comment)),
+//!             )
+//!     })
+//!     .bind(("127.0.0.1", 8080))?
+//!     .run()
+//!     .await
+//! # }
+//! ```
+
+pub use crate::hcaptcha_http::TokenSource;
+use crate::hcaptcha_http::{self, BodyFormat, ConfigSecret};
+use crate::{Code, Hcaptcha, HcaptchaClient, HcaptchaError, HcaptchaResponse, TrustedProxies};
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::StatusCode;
+use actix_web::{error, web, FromRequest, HttpMessage, HttpRequest, HttpResponse, ResponseError};
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::fmt;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "actix")))]
+impl ResponseError for HcaptchaError {
+    /// `400 Bad Request` for a missing or malformed token or remoteip,
+    /// `403 Forbidden` when the API rejects the token, `503 Service
+    /// Unavailable` when the API cannot be reached and `500 Internal Server
+    /// Error` otherwise, such as for an invalid secret.
+    fn status_code(&self) -> StatusCode {
+        StatusCode::from_u16(hcaptcha_http::error_status(self).as_u16())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// The details of the error are not included in the response.
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).body("hCaptcha verification failed")
+    }
+}
+
+/// Client and secret used by the [VerifiedHcaptcha] extractor.
+///
+/// Register the config as app data, directly or wrapped in
+/// [web::Data](actix_web::web::Data).
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "actix")))]
+#[derive(Clone)]
+pub struct HcaptchaConfig {
+    /// Client used to verify the token.
+    client: HcaptchaClient,
+    /// Secret of the site.
+    secret: ConfigSecret,
+    /// Proxies trusted to report the address of the client.
+    trusted_proxies: TrustedProxies,
+}
+
+impl fmt::Debug for HcaptchaConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HcaptchaConfig")
+            .field("client", &self.client)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl HcaptchaConfig {
+    /// Create a config verifying tokens with the client and secret.
+    pub fn new(client: HcaptchaClient, secret: &str) -> HcaptchaConfig {
+        HcaptchaConfig {
+            client,
+            secret: ConfigSecret::new(secret),
+            trusted_proxies: TrustedProxies::new(),
+        }
+    }
+
+    /// Set the proxies trusted to report the address of the client.
+    pub fn trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self
+    }
+
+    /// Get the client.
+    pub fn client(&self) -> &HcaptchaClient {
+        &self.client
+    }
+
+    /// Get the config registered as app data.
+    fn from_app_data(req: &HttpRequest) -> Option<HcaptchaConfig> {
+        req.app_data::<HcaptchaConfig>().cloned().or_else(|| {
+            req.app_data::<web::Data<HcaptchaConfig>>()
+                .map(|data| data.get_ref().clone())
+        })
+    }
+}
+
+/// Extractor for a struct deriving [Hcaptcha] whose token has been
+/// verified.
+///
+/// The struct is deserialized from a form
+/// (`application/x-www-form-urlencoded`) or JSON (`application/json`) body
+/// using the [FormConfig](actix_web::web::FormConfig) or
+/// [JsonConfig](actix_web::web::JsonConfig) of the app. The peer address of
+/// the connection is used when the `#[remoteip]` field is missing or empty.
+///
+/// The [HcaptchaConfig] must be registered as app data.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "actix")))]
+#[derive(Debug, Clone)]
+pub struct VerifiedHcaptcha<T> {
+    /// The deserialized body.
+    pub payload: T,
+    /// The response from the hcaptcha API.
+    pub response: HcaptchaResponse,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl<T> VerifiedHcaptcha<T> {
+    /// Get the deserialized body.
+    pub fn into_inner(self) -> T {
+        self.payload
+    }
+}
+
+impl<T> FromRequest for VerifiedHcaptcha<T>
+where
+    T: Hcaptcha + DeserializeOwned + 'static,
+{
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let config = match HcaptchaConfig::from_app_data(req) {
+            Some(config) => config,
+            None => {
+                return Box::pin(ready(Err(error::ErrorInternalServerError(
+                    "HcaptchaConfig is not registered as app data",
+                ))))
+            }
+        };
+        let remoteip = match client_ip(req, &config.trusted_proxies) {
+            Ok(remoteip) => remoteip,
+            Err(e) => return Box::pin(ready(Err(e.into()))),
+        };
+
+        let body: Pin<Box<dyn Future<Output = Result<T, actix_web::Error>>>> =
+            match hcaptcha_http::body_format(req.content_type()) {
+                Some(BodyFormat::Form) => {
+                    let form = web::Form::<T>::from_request(req, payload);
+                    Box::pin(async move { Ok(form.await?.into_inner()) })
+                }
+                Some(BodyFormat::Json) => {
+                    let json = web::Json::<T>::from_request(req, payload);
+                    Box::pin(async move { Ok(json.await?.into_inner()) })
+                }
+                None => Box::pin(ready(Err(error::ErrorUnsupportedMediaType(
+                    "Expected a form or JSON body",
+                )))),
+            };
+
+        Box::pin(async move {
+            let payload = body.await?;
+            let response = payload
+                .valid_response_with_remoteip(
+                    config.secret.get()?.expose(),
+                    &config.client,
+                    remoteip.as_deref(),
+                )
+                .await?;
+            Ok(VerifiedHcaptcha { payload, response })
+        })
+    }
+}
+
+/// Settings shared by the services of a middleware.
+#[derive(Clone)]
+struct MiddlewareConfig {
+    /// Client used to verify the token.
+    client: HcaptchaClient,
+    /// Secret of the site.
+    secret: ConfigSecret,
+    /// Location of the token in the request.
+    source: TokenSource,
+    /// Proxies trusted to report the address of the client.
+    trusted_proxies: TrustedProxies,
+}
+
+/// Middleware verifying the hcaptcha token of each request before it is
+/// handled.
+///
+/// On success the [HcaptchaResponse] is added to the request extensions,
+/// available to handlers as [web::ReqData](actix_web::web::ReqData).
+/// Otherwise the [HcaptchaError] is returned. A request without a token is
+/// rejected with [Code::MissingResponse].
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "actix")))]
+#[derive(Clone)]
+pub struct HcaptchaMiddleware {
+    config: MiddlewareConfig,
+}
+
+impl fmt::Debug for HcaptchaMiddleware {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HcaptchaMiddleware")
+            .field("client", &self.config.client)
+            .field("source", &self.config.source)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl HcaptchaMiddleware {
+    /// Create a middleware verifying the `h-captcha-response` field of the
+    /// body with the client and secret.
+    pub fn new(client: HcaptchaClient, secret: &str) -> HcaptchaMiddleware {
+        HcaptchaMiddleware {
+            config: MiddlewareConfig {
+                client,
+                secret: ConfigSecret::new(secret),
+                source: TokenSource::default(),
+                trusted_proxies: TrustedProxies::new(),
+            },
+        }
+    }
+
+    /// Set the location of the token in the request.
+    pub fn token_source(mut self, source: TokenSource) -> Self {
+        self.config.source = source;
+        self
+    }
+
+    /// Set the proxies trusted to report the address of the client.
+    pub fn trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.config.trusted_proxies = trusted_proxies;
+        self
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for HcaptchaMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Transform = HcaptchaMiddlewareService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(HcaptchaMiddlewareService {
+            service: Rc::new(service),
+            config: Rc::new(self.config.clone()),
+        }))
+    }
+}
+
+/// Service verifying the hcaptcha token of the request before it is
+/// handled.
+///
+/// Created by the [HcaptchaMiddleware].
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "actix")))]
+pub struct HcaptchaMiddlewareService<S> {
+    service: Rc<S>,
+    config: Rc<MiddlewareConfig>,
+}
+
+impl<S> fmt::Debug for HcaptchaMiddlewareService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HcaptchaMiddlewareService")
+            .field("source", &self.config.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, B> Service<ServiceRequest> for HcaptchaMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = actix_web::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let config = Rc::clone(&self.config);
+
+        Box::pin(async move {
+            let response = verify(&config, &mut req).await?;
+            req.extensions_mut().insert(response);
+            service.call(req).await
+        })
+    }
+}
+
+/// Verify the token of the request.
+#[cfg_attr(
+    feature = "trace",
+    tracing::instrument(
+        name = "Verify hcaptcha token before handling request.",
+        skip(config, req),
+        level = "debug"
+    )
+)]
+async fn verify(
+    config: &MiddlewareConfig,
+    req: &mut ServiceRequest,
+) -> Result<HcaptchaResponse, actix_web::Error> {
+    let token = match &config.source {
+        TokenSource::Header(name) => req
+            .headers()
+            .get(name.as_str())
+            .and_then(|value| value.to_str().ok())
+            .map(ToOwned::to_owned),
+        TokenSource::Query(field) => hcaptcha_http::query_field(Some(req.query_string()), field),
+        TokenSource::Body(field) => {
+            let format = hcaptcha_http::body_format(req.content_type())
+                .ok_or_else(|| error::ErrorUnsupportedMediaType("Expected a form or JSON body"))?;
+            let body = req.extract::<web::Bytes>().await?;
+            let token =
+                hcaptcha_http::body_field(format, &body, field).map_err(error::ErrorBadRequest)?;
+            req.set_payload(bytes_to_payload(body));
+            token
+        }
+    };
+    let token = token.ok_or_else(|| {
+        let mut codes = HashSet::new();
+        codes.insert(Code::MissingResponse);
+        HcaptchaError::Codes(codes)
+    })?;
+
+    let remoteip = client_ip(req.request(), &config.trusted_proxies)?;
+    let response =
+        hcaptcha_http::verify_token(&config.client, &config.secret, &token, remoteip.as_deref())
+            .await?;
+    Ok(response)
+}
+
+/// Get the address of the client from the peer address of the request and
+/// the headers set by trusted proxies.
+fn client_ip(
+    req: &HttpRequest,
+    trusted_proxies: &TrustedProxies,
+) -> Result<Option<String>, HcaptchaError> {
+    let peer = match req.peer_addr() {
+        Some(peer) => peer,
+        None => return Ok(None),
+    };
+    let headers = req
+        .headers()
+        .iter()
+        .map(|(name, value)| (name.as_str(), value.as_bytes()));
+    let remoteip = trusted_proxies.remoteip(headers, peer.ip())?;
+    Ok(Some(remoteip.to_string()))
+}
+
+/// Create a payload holding the buffered body.
+fn bytes_to_payload(body: web::Bytes) -> Payload {
+    let (_, mut payload) = actix_http::h1::Payload::create(true);
+    payload.unread_data(body);
+    Payload::from(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HcaptchaCaptcha, HcaptchaRequest, InMemoryTransport, TransportResponse};
+    use actix_web::{test, App};
+    use serde_json::json;
+    use std::net::SocketAddr;
+
+    const SECRET: &str = "0x123456789abcde0f123456789abcdef012345678";
+    const TOKEN: &str = "10000000-aaaa-bbbb-cccc-000000000001";
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Contact {
+        name: String,
+        #[serde(rename = "h-captcha-response")]
+        token: String,
+    }
+
+    impl Hcaptcha for Contact {
+        fn valid_response(
+            &self,
+            _secret: &str,
+            _uri: Option<String>,
+        ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>> {
+            unimplemented!("the tests verify with a client")
+        }
+
+        fn valid_response_with_client(
+            &self,
+            secret: &str,
+            client: &HcaptchaClient,
+        ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>> {
+            self.valid_response_with_remoteip(secret, client, None)
+        }
+
+        fn valid_response_with_remoteip(
+            &self,
+            secret: &str,
+            client: &HcaptchaClient,
+            remoteip: Option<&str>,
+        ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>> {
+            let request = HcaptchaCaptcha::new(&self.token)
+                .and_then(|captcha| match remoteip {
+                    Some(remoteip) => captcha.clone().set_remoteip(remoteip),
+                    None => Ok(captcha),
+                })
+                .and_then(|captcha| HcaptchaRequest::new(secret, captcha));
+            let client = client.clone();
+            Box::pin(async move { client.verify_client_response(request?).await })
+        }
+    }
+
+    fn client(success: bool) -> (HcaptchaClient, InMemoryTransport) {
+        let transport = InMemoryTransport::new();
+        let body = if success {
+            json!({ "success": true })
+        } else {
+            json!({ "success": false, "error-codes": ["invalid-input-response"] })
+        };
+        transport.push_response(TransportResponse::new(200, body.to_string()));
+        (HcaptchaClient::with_transport(transport.clone()), transport)
+    }
+
+    fn peer() -> SocketAddr {
+        SocketAddr::from(([192, 168, 0, 17], 4000))
+    }
+
+    #[tokio::test]
+    async fn extractor_verifies_form() {
+        let (client, transport) = client(true);
+        let (req, mut payload) = test::TestRequest::post()
+            .insert_header(("content-type", "application/x-www-form-urlencoded"))
+            .peer_addr(peer())
+            .app_data(HcaptchaConfig::new(client, SECRET))
+            .set_payload(format!("name=Alice&h-captcha-response={}", TOKEN))
+            .to_http_parts();
+
+        let verified = VerifiedHcaptcha::<Contact>::from_request(&req, &mut payload)
+            .await
+            .unwrap();
+        assert_eq!(verified.payload.name, "Alice");
+        assert!(verified.response.success());
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].1.contains("remoteip=192.168.0.17"));
+    }
+
+    #[tokio::test]
+    async fn extractor_returns_verification_error() {
+        let (client, _) = client(false);
+        let (req, mut payload) = test::TestRequest::post()
+            .insert_header(("content-type", "application/json"))
+            .app_data(web::Data::new(HcaptchaConfig::new(client, SECRET)))
+            .set_payload(json!({ "name": "Bob", "h-captcha-response": TOKEN }).to_string())
+            .to_http_parts();
+
+        let error = VerifiedHcaptcha::<Contact>::from_request(&req, &mut payload)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::FORBIDDEN
+        );
+    }
+
+    #[tokio::test]
+    async fn extractor_requires_config() {
+        let (req, mut payload) = test::TestRequest::post()
+            .insert_header(("content-type", "application/json"))
+            .set_payload(json!({ "name": "Bob", "h-captcha-response": TOKEN }).to_string())
+            .to_http_parts();
+
+        let error = VerifiedHcaptcha::<Contact>::from_request(&req, &mut payload)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
+    async fn echo(body: String, response: web::ReqData<HcaptchaResponse>) -> String {
+        assert!(response.success());
+        body
+    }
+
+    #[tokio::test]
+    async fn middleware_forwards_body_after_verification() {
+        let (client, transport) = client(true);
+        let app = test::init_service(
+            App::new()
+                .wrap(HcaptchaMiddleware::new(client, SECRET))
+                .route("/", web::post().to(echo)),
+        )
+        .await;
+        let body = format!("name=Alice&h-captcha-response={}", TOKEN);
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("content-type", "application/x-www-form-urlencoded"))
+            .peer_addr(peer())
+            .set_payload(body.clone())
+            .to_request();
+
+        let response = test::call_and_read_body(&app, req).await;
+        assert_eq!(response, body);
+        assert!(transport.requests()[0].1.contains("remoteip=192.168.0.17"));
+    }
+
+    #[tokio::test]
+    async fn middleware_reads_remoteip_from_trusted_proxy() {
+        let (client, transport) = client(true);
+        let proxies = TrustedProxies::new().trust("192.168.0.0/16").unwrap();
+        let app = test::init_service(
+            App::new()
+                .wrap(HcaptchaMiddleware::new(client, SECRET).trusted_proxies(proxies))
+                .route("/", web::post().to(echo)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("content-type", "application/x-www-form-urlencoded"))
+            .insert_header(("x-forwarded-for", "203.0.113.7, 192.168.0.2"))
+            .peer_addr(peer())
+            .set_payload(format!("h-captcha-response={}", TOKEN))
+            .to_request();
+
+        test::call_and_read_body(&app, req).await;
+        assert!(transport.requests()[0].1.contains("remoteip=203.0.113.7"));
+    }
+
+    #[tokio::test]
+    async fn middleware_reads_header() {
+        let (client, _) = client(true);
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    HcaptchaMiddleware::new(client, SECRET)
+                        .token_source(TokenSource::header("x-hcaptcha-token")),
+                )
+                .route("/", web::post().to(echo)),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/")
+            .insert_header(("x-hcaptcha-token", TOKEN))
+            .to_request();
+
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn middleware_rejects_missing_token() {
+        let (client, transport) = client(true);
+        let app = test::init_service(
+            App::new()
+                .wrap(
+                    HcaptchaMiddleware::new(client, SECRET)
+                        .token_source(TokenSource::query("token")),
+                )
+                .route("/", web::post().to(echo)),
+        )
+        .await;
+        let req = test::TestRequest::post().uri("/").to_request();
+
+        let error = test::try_call_service(&app, req).await.unwrap_err();
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::BAD_REQUEST
+        );
+        assert!(transport.requests().is_empty());
+    }
+}
@@ -0,0 +1,421 @@
+//! Axum extractor verifying the hcaptcha token before the handler runs.
+//!
+//! The [VerifiedHcaptcha] extractor reads a form or JSON body, takes the
+//! token from its `h-captcha-response` field and verifies the token using the
+//! [HcaptchaConfig] held in the application state. The address of the
+//! connection, or of the client reported by a trusted proxy, is sent as the
+//! remoteip when the router is served with connect info. The handler only
+//! runs if the verification succeeds.
+//!
+//! # Example
+//! ```no_run
+//!     use axum::{routing::post, Router};
+//!     use hcaptcha::axum::{HcaptchaConfig, VerifiedHcaptcha};
+//!     use hcaptcha::HcaptchaClient;
+//!     use std::net::SocketAddr;
+//!
+//!     #[derive(serde::Deserialize)]
+//!     struct ContactForm {
+//!         name: String,
+//!     }
+//!
+//!     async fn contact(form: VerifiedHcaptcha<ContactForm>) -> String {
+//!         format!("{}, thank you for your contact request.", form.payload.name)
+//!     }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let config = HcaptchaConfig::new(
+//!         HcaptchaClient::new(),
+//!         "0x123456789abcde0f123456789abcdef012345678",
+//!     );
+//!     let app = Router::new()
+//!         .route("/contact", post(contact))
+//!         .with_state(config);
+//!
+//!     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
+//!     axum::serve(
+//!         listener,
+//!         app.into_make_service_with_connect_info::<SocketAddr>(),
+//!     )
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::hcaptcha_http::{self, BodyFormat, ConfigSecret};
+use crate::{HcaptchaClient, HcaptchaError, HcaptchaResponse, TrustedProxies};
+use ::axum::body::Bytes;
+use ::axum::extract::{ConnectInfo, FromRef, FromRequest, Request};
+use ::axum::http::{header, StatusCode};
+use ::axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Function building the response for a rejected request.
+type RejectionHandler = dyn Fn(HcaptchaRejection) -> Response + Send + Sync;
+
+/// Client, secret and rejection handler used by the [VerifiedHcaptcha]
+/// extractor.
+///
+/// Use the config as the state of the router, or implement
+/// [FromRef](::axum::extract::FromRef) for the application state to provide
+/// it. Clones share the client and the rejection handler.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+#[derive(Clone)]
+pub struct HcaptchaConfig {
+    /// Client used to verify the token.
+    client: HcaptchaClient,
+    /// Secret of the site.
+    secret: ConfigSecret,
+    /// Builds the response for a rejected request.
+    on_rejection: Arc<RejectionHandler>,
+    /// Proxies trusted to report the address of the client.
+    trusted_proxies: TrustedProxies,
+}
+
+impl fmt::Debug for HcaptchaConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HcaptchaConfig")
+            .field("client", &self.client)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl HcaptchaConfig {
+    /// Create a config verifying tokens with the client and secret.
+    ///
+    /// Rejected requests receive the default response of the
+    /// [HcaptchaRejection].
+    pub fn new(client: HcaptchaClient, secret: &str) -> HcaptchaConfig {
+        HcaptchaConfig {
+            client,
+            secret: ConfigSecret::new(secret),
+            on_rejection: Arc::new(IntoResponse::into_response),
+            trusted_proxies: TrustedProxies::new(),
+        }
+    }
+
+    /// Set the proxies trusted to report the address of the client.
+    ///
+    /// The remoteip of a request arriving from a trusted proxy is read from
+    /// the header of the [TrustedProxies].
+    pub fn trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.trusted_proxies = trusted_proxies;
+        self
+    }
+
+    /// Set the function building the response for a rejected request.
+    pub fn on_rejection<F>(mut self, on_rejection: F) -> Self
+    where
+        F: Fn(HcaptchaRejection) -> Response + Send + Sync + 'static,
+    {
+        self.on_rejection = Arc::new(on_rejection);
+        self
+    }
+
+    /// Get the client.
+    pub fn client(&self) -> &HcaptchaClient {
+        &self.client
+    }
+}
+
+/// Reason a request was rejected by the [VerifiedHcaptcha] extractor.
+///
+/// The default response is `415 Unsupported Media Type` for a body that is
+/// not a form or JSON, `400 Bad Request` for a body that cannot be read or
+/// has no token. A failed verification responds `400 Bad Request` for a
+/// malformed token or remoteip, `403 Forbidden` when the API rejects the
+/// token, `503 Service Unavailable` when the API cannot be reached and
+/// `500 Internal Server Error` otherwise, such as for an invalid secret. The
+/// details of the error are not included in the default response.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum HcaptchaRejection {
+    /// The body is neither a form nor JSON.
+    #[error("Expected a form or JSON body")]
+    UnsupportedMediaType,
+    /// The body could not be read or deserialized.
+    #[error("The body could not be read: {0}")]
+    InvalidBody(String),
+    /// The body has no `h-captcha-response` field.
+    #[error("The body has no h-captcha-response field")]
+    MissingToken,
+    /// The token could not be verified.
+    #[error("The hcaptcha token could not be verified: {0}")]
+    Verification(HcaptchaError),
+}
+
+impl HcaptchaRejection {
+    /// Status code of the default response.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            HcaptchaRejection::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            HcaptchaRejection::InvalidBody(_) | HcaptchaRejection::MissingToken => {
+                StatusCode::BAD_REQUEST
+            }
+            HcaptchaRejection::Verification(error) => hcaptcha_http::error_status(error),
+        }
+    }
+}
+
+impl IntoResponse for HcaptchaRejection {
+    fn into_response(self) -> Response {
+        let body = match &self {
+            HcaptchaRejection::Verification(_) => "hCaptcha verification failed".to_owned(),
+            rejection => rejection.to_string(),
+        };
+        (self.status(), body).into_response()
+    }
+}
+
+/// Extractor for a body whose hcaptcha token has been verified.
+///
+/// The body is deserialized into the payload from a form
+/// (`application/x-www-form-urlencoded`) or JSON (`application/json`)
+/// body. The token is read from the `h-captcha-response` field of the same
+/// body, so the payload must not deny unknown fields.
+///
+/// The [HcaptchaConfig] is taken from the state of the router. A rejected
+/// request receives the response built by
+/// [on_rejection](HcaptchaConfig::on_rejection).
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+#[derive(Debug, Clone)]
+pub struct VerifiedHcaptcha<T> {
+    /// The deserialized body.
+    pub payload: T,
+    /// The response from the hcaptcha API.
+    pub response: HcaptchaResponse,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl<T> VerifiedHcaptcha<T> {
+    /// Get the deserialized body.
+    pub fn into_inner(self) -> T {
+        self.payload
+    }
+}
+
+impl<S, T> FromRequest<S> for VerifiedHcaptcha<T>
+where
+    HcaptchaConfig: FromRef<S>,
+    S: Send + Sync,
+    T: DeserializeOwned + Send,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let config = HcaptchaConfig::from_ref(state);
+        verify(req, state, &config)
+            .await
+            .map_err(|rejection| (config.on_rejection)(rejection))
+    }
+}
+
+/// Deserialize the body and verify its token.
+#[cfg_attr(
+    feature = "trace",
+    tracing::instrument(
+        name = "Verify hcaptcha token in request body.",
+        skip(req, state, config),
+        level = "debug"
+    )
+)]
+async fn verify<S, T>(
+    req: Request,
+    state: &S,
+    config: &HcaptchaConfig,
+) -> Result<VerifiedHcaptcha<T>, HcaptchaRejection>
+where
+    S: Send + Sync,
+    T: DeserializeOwned,
+{
+    let format = req
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(hcaptcha_http::body_format)
+        .ok_or(HcaptchaRejection::UnsupportedMediaType)?;
+    let remoteip = match req.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => Some(
+            config
+                .trusted_proxies
+                .remoteip_from_headers(req.headers(), addr.ip())
+                .map_err(HcaptchaRejection::Verification)?
+                .to_string(),
+        ),
+        None => None,
+    };
+    let body = Bytes::from_request(req, state)
+        .await
+        .map_err(|e| HcaptchaRejection::InvalidBody(e.body_text()))?;
+
+    let payload: T = match format {
+        BodyFormat::Form => serde_urlencoded::from_bytes(&body)
+            .map_err(|e| HcaptchaRejection::InvalidBody(e.to_string()))?,
+        BodyFormat::Json => serde_json::from_slice(&body)
+            .map_err(|e| HcaptchaRejection::InvalidBody(e.to_string()))?,
+    };
+    let token = hcaptcha_http::body_field(format, &body, "h-captcha-response")
+        .map_err(HcaptchaRejection::InvalidBody)?
+        .ok_or(HcaptchaRejection::MissingToken)?;
+
+    let response =
+        hcaptcha_http::verify_token(&config.client, &config.secret, &token, remoteip.as_deref())
+            .await
+            .map_err(HcaptchaRejection::Verification)?;
+
+    Ok(VerifiedHcaptcha { payload, response })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryTransport, TransportResponse};
+    use ::axum::body::Body;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    const SECRET: &str = "0x123456789abcde0f123456789abcdef012345678";
+    const TOKEN: &str = "10000000-aaaa-bbbb-cccc-000000000001";
+
+    #[derive(Debug, Deserialize)]
+    struct Contact {
+        name: String,
+    }
+
+    fn config(success: bool) -> (HcaptchaConfig, InMemoryTransport) {
+        let transport = InMemoryTransport::new();
+        let body = if success {
+            json!({ "success": true })
+        } else {
+            json!({ "success": false, "error-codes": ["invalid-input-response"] })
+        };
+        transport.push_response(TransportResponse::new(200, body.to_string()));
+        let client = HcaptchaClient::with_transport(transport.clone());
+        (HcaptchaConfig::new(client, SECRET), transport)
+    }
+
+    fn request(content_type: &str, body: String) -> Request {
+        let mut req = Request::builder()
+            .method("POST")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([192, 168, 0, 17], 4000))));
+        req
+    }
+
+    #[tokio::test]
+    async fn form_body_is_verified() {
+        let (config, transport) = config(true);
+        let req = request(
+            "application/x-www-form-urlencoded",
+            format!("name=Alice&h-captcha-response={}", TOKEN),
+        );
+
+        let verified = VerifiedHcaptcha::<Contact>::from_request(req, &config)
+            .await
+            .unwrap();
+        assert_eq!(verified.payload.name, "Alice");
+        assert!(verified.response.success());
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].1.contains("remoteip=192.168.0.17"));
+    }
+
+    #[tokio::test]
+    async fn trusted_proxy_reports_remoteip() {
+        let (config, transport) = config(true);
+        let config = config.trusted_proxies(TrustedProxies::new().trust("192.168.0.0/16").unwrap());
+        let mut req = request(
+            "application/x-www-form-urlencoded",
+            format!("name=Alice&h-captcha-response={}", TOKEN),
+        );
+        req.headers_mut()
+            .insert("x-forwarded-for", "203.0.113.7".parse().unwrap());
+
+        VerifiedHcaptcha::<Contact>::from_request(req, &config)
+            .await
+            .unwrap();
+        assert!(transport.requests()[0].1.contains("remoteip=203.0.113.7"));
+    }
+
+    #[tokio::test]
+    async fn json_body_is_verified() {
+        let (config, _) = config(true);
+        let req = request(
+            "application/json; charset=utf-8",
+            json!({ "name": "Bob", "h-captcha-response": TOKEN }).to_string(),
+        );
+
+        let verified = VerifiedHcaptcha::<Contact>::from_request(req, &config)
+            .await
+            .unwrap();
+        assert_eq!(verified.into_inner().name, "Bob");
+    }
+
+    #[tokio::test]
+    async fn missing_token_is_bad_request() {
+        let (config, transport) = config(true);
+        let req = request("application/json", json!({ "name": "Bob" }).to_string());
+
+        let response = VerifiedHcaptcha::<Contact>::from_request(req, &config)
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unsupported_media_type() {
+        let (config, _) = config(true);
+        let req = request("text/plain", "name=Bob".to_owned());
+
+        let response = VerifiedHcaptcha::<Contact>::from_request(req, &config)
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    #[tokio::test]
+    async fn failed_verification_is_forbidden() {
+        let (config, _) = config(false);
+        let req = request(
+            "application/json",
+            json!({ "name": "Bob", "h-captcha-response": TOKEN }).to_string(),
+        );
+
+        let response = VerifiedHcaptcha::<Contact>::from_request(req, &config)
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn rejection_response_is_configurable() {
+        let (config, _) = config(false);
+        let config = config.on_rejection(|rejection| match rejection {
+            HcaptchaRejection::Verification(_) => StatusCode::UNAUTHORIZED.into_response(),
+            rejection => rejection.into_response(),
+        });
+        let req = request(
+            "application/json",
+            json!({ "name": "Bob", "h-captcha-response": TOKEN }).to_string(),
+        );
+
+        let response = VerifiedHcaptcha::<Contact>::from_request(req, &config)
+            .await
+            .unwrap_err();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+}
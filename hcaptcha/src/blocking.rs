@@ -0,0 +1,43 @@
+//! Blocking verification of the hcaptcha response.
+//!
+//! The blocking [HcaptchaClient] and [Hcaptcha] trait verify a response
+//! without an async runtime, for use in synchronous programs such as command
+//! line tools or thread per request services. The request and response types
+//! are the same as for the async client.
+//!
+//! Each blocking client runs the async client on its own single threaded
+//! runtime. The blocking client must not be used from within an async
+//! runtime.
+//!
+//! Available with the `blocking` feature.
+//!
+//! # Example
+//! Derive the Hcaptcha trait and verify the response with a blocking client.
+//! ```no_run
+//! # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::blocking::{Hcaptcha, HcaptchaClient};
+//!
+//!     #[derive(Hcaptcha)]
+//!     struct ContactForm {
+//!         name: String,
+//!         #[captcha]
+//!         token: String,
+//!     }
+//!
+//! #   let secret = "0x123456789abcde0f123456789abcdef012345678";
+//! #   let form = ContactForm {
+//! #       name: "name".to_owned(),
+//! #       token: "10000000-aaaa-bbbb-cccc-000000000001".to_owned(),
+//! #   };
+//!     let client = HcaptchaClient::new();
+//!     let response = form.valid_response_with_client(secret, &client)?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod hcaptcha;
+mod hcaptcha_client;
+
+pub use self::hcaptcha::Hcaptcha;
+pub use hcaptcha_client::HcaptchaClient;
+pub use hcaptcha_derive::Hcaptcha;
@@ -0,0 +1,116 @@
+//! Blocking Hcaptcha Trait
+use super::HcaptchaClient;
+use crate::{HcaptchaError, HcaptchaResponse};
+
+/// Blocking Hcaptcha trait
+///
+/// Implemented for every type implementing the async
+/// [Hcaptcha](crate::Hcaptcha) trait, so deriving `Hcaptcha` provides both.
+/// Import only one of the traits to avoid ambiguous method calls.
+#[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+pub trait Hcaptcha {
+    /// valid response function
+    ///
+    /// A new blocking [HcaptchaClient] is created for each call. Use
+    /// [valid_response_with_client](Hcaptcha::valid_response_with_client)
+    /// to reuse an existing client.
+    ///
+    /// Without the `reqwest` feature there is no default transport and an
+    /// error is returned.
+    fn valid_response(
+        &self,
+        secret: &str,
+        uri: Option<String>,
+    ) -> Result<HcaptchaResponse, HcaptchaError>;
+
+    /// valid response function using an existing blocking client
+    fn valid_response_with_client(
+        &self,
+        secret: &str,
+        client: &HcaptchaClient,
+    ) -> Result<HcaptchaResponse, HcaptchaError>;
+}
+
+impl<T> Hcaptcha for T
+where
+    T: crate::Hcaptcha + ?Sized,
+{
+    fn valid_response(
+        &self,
+        secret: &str,
+        uri: Option<String>,
+    ) -> Result<HcaptchaResponse, HcaptchaError> {
+        let mut builder = crate::HcaptchaClient::builder();
+        if let Some(u) = &uri {
+            builder = builder.url(u);
+        }
+        let client = HcaptchaClient::from_async(builder.build()?)?;
+        Hcaptcha::valid_response_with_client(self, secret, &client)
+    }
+
+    fn valid_response_with_client(
+        &self,
+        secret: &str,
+        client: &HcaptchaClient,
+    ) -> Result<HcaptchaResponse, HcaptchaError> {
+        client.block_on(crate::Hcaptcha::valid_response_with_client(
+            self,
+            secret,
+            client.as_async(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HcaptchaCaptcha, HcaptchaRequest, InMemoryTransport, TransportResponse};
+    use serde_json::json;
+    use std::{future::Future, pin::Pin};
+
+    struct Form {
+        token: String,
+    }
+
+    impl crate::Hcaptcha for Form {
+        fn valid_response(
+            &self,
+            _secret: &str,
+            _uri: Option<String>,
+        ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>> {
+            unimplemented!("the tests verify with a client")
+        }
+
+        fn valid_response_with_client(
+            &self,
+            secret: &str,
+            client: &crate::HcaptchaClient,
+        ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>> {
+            let request = HcaptchaCaptcha::new(&self.token)
+                .and_then(|captcha| HcaptchaRequest::new(secret, captcha));
+            let client = client.clone();
+            Box::pin(async move { client.verify_client_response(request?).await })
+        }
+    }
+
+    #[test]
+    fn blocking_trait_verifies_response() {
+        let transport = InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": true }).to_string(),
+        ));
+        let client = HcaptchaClient::with_transport(transport.clone()).unwrap();
+        let form = Form {
+            token: "10000000-aaaa-bbbb-cccc-000000000001".to_owned(),
+        };
+
+        let response = Hcaptcha::valid_response_with_client(
+            &form,
+            "0x123456789abcde0f123456789abcdef012345678",
+            &client,
+        );
+        assert!(response.unwrap().success());
+        assert_eq!(transport.requests().len(), 1);
+    }
+}
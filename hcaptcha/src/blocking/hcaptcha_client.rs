@@ -0,0 +1,247 @@
+//! Blocking client to submit a request to the Hcaptcha API.
+//!
+//! # Example
+//! Create a blocking client from an async client with a deadline.
+//! ```
+//! # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::blocking::HcaptchaClient;
+//!     use std::time::Duration;
+//!
+//!     let client = hcaptcha::HcaptchaClient::builder()
+//!         .deadline(Duration::from_secs(5))
+//!         .build()?;
+//!     let client = HcaptchaClient::from_async(client)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{HcaptchaError, HcaptchaRequest, HcaptchaResponse, HcaptchaTransport};
+use std::sync::Arc;
+use tokio::runtime::{Builder, Runtime};
+
+/// Blocking client to submit a request to a Hcaptcha validation endpoint.
+///
+/// The client wraps an async [HcaptchaClient](crate::HcaptchaClient) and the
+/// runtime used to drive it. Clones share the runtime and the transport.
+///
+/// # Panic
+///
+/// The verification methods panic if called from within an async runtime.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+#[derive(Debug, Clone)]
+pub struct HcaptchaClient {
+    /// The async client.
+    inner: crate::HcaptchaClient,
+    /// Runtime to drive the async client.
+    runtime: Arc<Runtime>,
+}
+
+#[cfg(feature = "reqwest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl Default for HcaptchaClient {
+    fn default() -> HcaptchaClient {
+        HcaptchaClient::new()
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl HcaptchaClient {
+    /// Create a new blocking client to connect with the default Hcaptcha
+    /// siteverify API endpoint specified in [VERIFY_URL](crate::VERIFY_URL).
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::blocking::HcaptchaClient;
+    ///     let client = HcaptchaClient::new();
+    /// ```
+    /// # Panic
+    ///
+    /// If the runtime cannot be created the function will panic.
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+    pub fn new() -> HcaptchaClient {
+        HcaptchaClient::from_async(crate::HcaptchaClient::new())
+            .expect("failed to create runtime for blocking client")
+    }
+
+    /// Create a new blocking client and specify the url for the API.
+    ///
+    /// # Example
+    /// ```
+    ///     use hcaptcha::blocking::HcaptchaClient;
+    ///
+    ///     let url = "https://domain.com/siteverify";
+    ///     let _client = HcaptchaClient::new_with(url);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError] is returned if the url cannot be parsed or the
+    /// runtime cannot be created.
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+    pub fn new_with(url: &str) -> Result<HcaptchaClient, HcaptchaError> {
+        HcaptchaClient::from_async(crate::HcaptchaClient::new_with(url)?)
+    }
+
+    /// Create a new blocking client using the transport.
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError] is returned if the runtime cannot be created.
+    pub fn with_transport<T>(transport: T) -> Result<HcaptchaClient, HcaptchaError>
+    where
+        T: HcaptchaTransport + 'static,
+    {
+        HcaptchaClient::from_async(crate::HcaptchaClient::with_transport(transport))
+    }
+
+    /// Create a blocking client from an async client.
+    ///
+    /// Use the [HcaptchaClientBuilder](crate::HcaptchaClientBuilder) to
+    /// configure timeouts, retries and the transport of the async client.
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError] is returned if the runtime cannot be created.
+    pub fn from_async(client: crate::HcaptchaClient) -> Result<HcaptchaClient, HcaptchaError> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(HcaptchaClient {
+            inner: client,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    /// Set the url.
+    ///
+    /// Specify the url for the hcaptcha API.
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError] is returned if the url cannot be parsed.
+    pub fn set_url(mut self, url: &str) -> Result<Self, HcaptchaError> {
+        self.inner = self.inner.set_url(url)?;
+        Ok(self)
+    }
+
+    /// Get the async client.
+    pub fn as_async(&self) -> &crate::HcaptchaClient {
+        &self.inner
+    }
+
+    /// Verify the client token with the Hcaptcha API
+    ///
+    /// Blocks the current thread until the verification completes. See
+    /// [HcaptchaClient::verify_client_response](crate::HcaptchaClient::verify_client_response).
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError] is returned if the verification fails.
+    ///
+    /// # Panic
+    ///
+    /// The function panics if called from within an async runtime.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Request blocking verification from hcaptcha.",
+            skip(self),
+            level = "debug"
+        )
+    )]
+    pub fn verify_client_response(
+        &self,
+        request: HcaptchaRequest,
+    ) -> Result<HcaptchaResponse, HcaptchaError> {
+        self.block_on(self.inner.verify_client_response(request))
+    }
+
+    /// Verify many requests concurrently and return the results in the order
+    /// of the requests.
+    ///
+    /// Blocks the current thread until all the verifications complete. See
+    /// [HcaptchaClient::verify_many](crate::HcaptchaClient::verify_many).
+    ///
+    /// # Panic
+    ///
+    /// The function panics if called from within an async runtime.
+    pub fn verify_many<I>(
+        &self,
+        requests: I,
+        concurrency: usize,
+    ) -> Vec<Result<HcaptchaResponse, HcaptchaError>>
+    where
+        I: IntoIterator<Item = HcaptchaRequest>,
+    {
+        self.block_on(self.inner.verify_many(requests, concurrency))
+    }
+
+    /// Run the future to completion on the runtime of the client.
+    pub(crate) fn block_on<F: std::future::Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryTransport, TransportResponse};
+    use claims::{assert_err, assert_ok};
+    use serde_json::json;
+
+    fn request() -> HcaptchaRequest {
+        HcaptchaRequest::new_from_response(
+            "0x123456789abcde0f123456789abcdef012345678",
+            "10000000-aaaa-bbbb-cccc-000000000001",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn blocking_verification() {
+        let transport = InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": true, "hostname": "test-host" }).to_string(),
+        ));
+        let client = HcaptchaClient::with_transport(transport.clone()).unwrap();
+
+        let response = client.verify_client_response(request());
+        assert_ok!(&response);
+        assert!(response.unwrap().success());
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[test]
+    fn blocking_verification_of_many_requests() {
+        let transport = InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": true }).to_string(),
+        ));
+        transport.push_response(TransportResponse::new(503, ""));
+        let client = HcaptchaClient::with_transport(transport.clone()).unwrap();
+
+        let results = client.verify_many(vec![request(), request()], 1);
+        assert_ok!(&results[0]);
+        assert_err!(&results[1]);
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[test]
+    fn blocking_verification_error() {
+        let transport = InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": false, "error-codes": ["invalid-input-response"] }).to_string(),
+        ));
+        let client = HcaptchaClient::with_transport(transport)
+            .unwrap()
+            .set_url("https://domain.com/siteverify")
+            .unwrap();
+
+        assert_err!(client.verify_client_response(request()));
+    }
+}
@@ -7,7 +7,7 @@ mod hcaptcha_secret_ext;
 mod hcaptcha_sitekey;
 
 pub(crate) use hcaptcha_client_response::HcaptchaClientResponse;
-pub(crate) use hcaptcha_remoteip::HcaptchaRemoteip;
+pub use hcaptcha_remoteip::HcaptchaRemoteip;
 #[cfg(not(feature = "ext"))]
 pub(crate) use hcaptcha_secret::HcaptchaSecret;
 #[cfg(feature = "ext")]
@@ -2,9 +2,22 @@ use crate::{Code, HcaptchaError};
 use std::collections::HashSet;
 use std::fmt;
 
-#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+/// Text shown in place of the token.
+const REDACTED: &str = "[REDACTED]";
+
+/// The token is redacted when debug formatted, so it is not recorded in
+/// traced output.
+#[derive(Default, Clone, serde::Deserialize, serde::Serialize)]
 pub struct HcaptchaClientResponse(String);
 
+impl fmt::Debug for HcaptchaClientResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HcaptchaClientResponse")
+            .field(&REDACTED)
+            .finish()
+    }
+}
+
 impl HcaptchaClientResponse {
     pub fn parse(s: String) -> Result<HcaptchaClientResponse, HcaptchaError> {
         if s.trim().is_empty() {
@@ -35,6 +48,14 @@ mod tests {
     use crate::HcaptchaError;
     use claims::assert_err;
 
+    #[test]
+    fn token_is_redacted_from_debug() {
+        let token = "10000000-aaaa-bbbb-cccc-000000000001";
+        let response = HcaptchaClientResponse::parse(token.to_string()).unwrap();
+        assert!(!format!("{:?}", response).contains(token));
+        assert_eq!(response.to_string(), token);
+    }
+
     #[test]
     fn whitespace_only_names_are_rejected() {
         let response = " ".to_string();
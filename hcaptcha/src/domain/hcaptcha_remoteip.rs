@@ -1,11 +1,30 @@
 use crate::{Code, HcaptchaError};
 use std::collections::HashSet;
+use std::convert::TryFrom;
 use std::fmt;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::str::FromStr;
 
-#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
-pub struct HcaptchaRemoteip(String);
+/// A validated ip address of the user, sent to the API as the remoteip.
+///
+/// The address is normalised when created: the port and any IPv6 zone id
+/// are dropped, and an IPv4-mapped IPv6 address is stored as the IPv4
+/// address.
+///
+/// Displayed as the address string, which can be used for the `#[remoteip]`
+/// field of a struct deriving [Hcaptcha](crate::Hcaptcha).
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(from = "IpAddr")]
+pub struct HcaptchaRemoteip(IpAddr);
+
+/// The unspecified IPv4 address, `0.0.0.0`.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl Default for HcaptchaRemoteip {
+    fn default() -> Self {
+        HcaptchaRemoteip::from(Ipv4Addr::UNSPECIFIED)
+    }
+}
 
 impl fmt::Display for HcaptchaRemoteip {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -13,16 +32,92 @@ impl fmt::Display for HcaptchaRemoteip {
     }
 }
 
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
 impl HcaptchaRemoteip {
+    /// Parse the string as an ip address.
+    ///
+    /// The string may include a port, as in `192.0.2.1:4711` or
+    /// `[2001:db8::1]:4711`, and an IPv6 zone id, as in `fe80::1%eth0`.
+    ///
+    /// # Errors
+    ///
+    /// An [HcaptchaError::Codes] containing [Code::MissingUserIp] for an
+    /// empty string, or [Code::InvalidUserIp] if the string is not an ip
+    /// address.
     #[cfg_attr(
         feature = "trace",
         tracing::instrument(name = "Validate User IP.", skip(s), level = "debug")
     )]
     pub fn parse(s: String) -> Result<Self, HcaptchaError> {
         empty_ip_string(&s)?;
-        invalid_ip_string(&s)?;
+        let ip = invalid_ip_string(s.trim())?;
+
+        Ok(HcaptchaRemoteip::from(ip))
+    }
+
+    /// Get the ip address.
+    pub fn ip(&self) -> IpAddr {
+        self.0
+    }
+}
+
+impl From<IpAddr> for HcaptchaRemoteip {
+    fn from(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+                Some(v4) => HcaptchaRemoteip(IpAddr::V4(v4)),
+                None => HcaptchaRemoteip(ip),
+            },
+            IpAddr::V4(_) => HcaptchaRemoteip(ip),
+        }
+    }
+}
+
+impl From<Ipv4Addr> for HcaptchaRemoteip {
+    fn from(ip: Ipv4Addr) -> Self {
+        HcaptchaRemoteip(IpAddr::V4(ip))
+    }
+}
+
+impl From<Ipv6Addr> for HcaptchaRemoteip {
+    fn from(ip: Ipv6Addr) -> Self {
+        HcaptchaRemoteip::from(IpAddr::V6(ip))
+    }
+}
+
+impl From<SocketAddr> for HcaptchaRemoteip {
+    fn from(addr: SocketAddr) -> Self {
+        HcaptchaRemoteip::from(addr.ip())
+    }
+}
+
+impl From<HcaptchaRemoteip> for IpAddr {
+    fn from(remoteip: HcaptchaRemoteip) -> Self {
+        remoteip.0
+    }
+}
+
+impl TryFrom<&str> for HcaptchaRemoteip {
+    type Error = HcaptchaError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        HcaptchaRemoteip::parse(s.to_owned())
+    }
+}
+
+impl TryFrom<String> for HcaptchaRemoteip {
+    type Error = HcaptchaError;
 
-        Ok(HcaptchaRemoteip(s))
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        HcaptchaRemoteip::parse(s)
+    }
+}
+
+impl FromStr for HcaptchaRemoteip {
+    type Err = HcaptchaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        HcaptchaRemoteip::parse(s.to_owned())
     }
 }
 
@@ -47,19 +142,35 @@ fn empty_ip_string(s: &str) -> Result<(), HcaptchaError> {
     feature = "trace",
     tracing::instrument(name = "Return error if not an ip string.", skip(s), level = "debug")
 )]
-fn invalid_ip_string(s: &str) -> Result<(), HcaptchaError> {
-    let invalid_ip4 = Ipv4Addr::from_str(s).is_err();
-    let invalid_ip6 = Ipv6Addr::from_str(s).is_err();
-    if invalid_ip4 && invalid_ip6 {
+fn invalid_ip_string(s: &str) -> Result<IpAddr, HcaptchaError> {
+    let host = match s.strip_prefix('[') {
+        // [2001:db8::1]:4711
+        Some(rest) => match rest.split_once(']') {
+            Some((host, "")) => Some(host),
+            Some((host, tail)) => tail
+                .strip_prefix(':')
+                .filter(|port| u16::from_str(port).is_ok())
+                .map(|_| host),
+            None => None,
+        },
+        // 192.0.2.1:4711, an IPv6 address has more than one colon
+        None if s.matches(':').count() == 1 => s
+            .split_once(':')
+            .filter(|(_, port)| u16::from_str(port).is_ok())
+            .map(|(host, _)| host),
+        None => Some(s),
+    };
+    // fe80::1%eth0
+    let host = host.map(|h| h.split('%').next().unwrap_or_default());
+
+    host.and_then(|h| IpAddr::from_str(h).ok()).ok_or_else(|| {
         let mut codes = HashSet::new();
         codes.insert(Code::InvalidUserIp);
 
         #[cfg(feature = "trace")]
         tracing::debug!("UserIP string is invalid");
-        Err(HcaptchaError::Codes(codes))
-    } else {
-        Ok(())
-    }
+        HcaptchaError::Codes(codes)
+    })
 }
 
 #[cfg(test)]
@@ -68,6 +179,13 @@ mod tests {
     use crate::Code;
     use crate::HcaptchaError;
     use claims::{assert_err, assert_ok};
+    use std::convert::TryFrom;
+    use std::net::{IpAddr, SocketAddr};
+
+    #[test]
+    fn default_is_the_unspecified_address() {
+        assert_eq!(HcaptchaRemoteip::default().to_string(), "0.0.0.0");
+    }
 
     #[test]
     fn whitespace_only_ip_strings_are_rejected() {
@@ -112,4 +230,56 @@ mod tests {
         let ip_string = mockd::internet::ipv6_address();
         assert_ok!(HcaptchaRemoteip::parse(ip_string));
     }
+
+    #[test]
+    fn ports_and_zone_ids_are_stripped() {
+        for (s, expected) in [
+            ("192.0.2.1:4711", "192.0.2.1"),
+            ("[2001:db8::1]:4711", "2001:db8::1"),
+            ("[2001:db8::1]", "2001:db8::1"),
+            ("fe80::1%eth0", "fe80::1"),
+            ("[fe80::1%25]:80", "fe80::1"),
+            (" 192.0.2.1 ", "192.0.2.1"),
+        ] {
+            let remoteip = HcaptchaRemoteip::parse(s.to_owned()).unwrap();
+            assert_eq!(remoteip.to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn invalid_ports_are_rejected() {
+        for s in [
+            "[2001:db8::1]garbage",
+            "[2001:db8::1]:",
+            "[2001:db8::1]:65536",
+            "192.0.2.1:abc",
+            "192.0.2.1:",
+        ] {
+            assert_err!(HcaptchaRemoteip::parse(s.to_owned()));
+        }
+    }
+
+    #[test]
+    fn ipv4_mapped_addresses_are_unmapped() {
+        let remoteip = HcaptchaRemoteip::parse("::ffff:192.0.2.1".to_owned()).unwrap();
+        assert_eq!(remoteip.ip(), IpAddr::from([192, 0, 2, 1]));
+
+        let addr: SocketAddr = "[::ffff:192.0.2.1]:4711".parse().unwrap();
+        assert_eq!(HcaptchaRemoteip::from(addr).to_string(), "192.0.2.1");
+
+        let remoteip: HcaptchaRemoteip = serde_json::from_str(r#""::ffff:192.0.2.1""#).unwrap();
+        assert_eq!(remoteip.to_string(), "192.0.2.1");
+    }
+
+    #[test]
+    fn converts_from_and_into_ip_addr() {
+        let ip = IpAddr::from([198, 51, 100, 1]);
+        let remoteip = HcaptchaRemoteip::from(ip);
+        assert_eq!(IpAddr::from(remoteip), ip);
+        assert_eq!(
+            HcaptchaRemoteip::try_from("198.51.100.1").unwrap(),
+            remoteip
+        );
+        assert_err!(HcaptchaRemoteip::try_from("198.51.100"));
+    }
 }
@@ -1,22 +1,49 @@
 use crate::{Code, HcaptchaError};
 use std::collections::HashSet;
 use std::fmt;
+use zeroize::Zeroizing;
 
-#[derive(Debug, Default, Clone, serde::Serialize)]
-pub struct HcaptchaSecret(String);
+/// Text shown in place of the secret.
+const REDACTED: &str = "[REDACTED]";
+
+/// The secret is zeroed when dropped and redacted when formatted. It is only
+/// exposed to build the form sent to the API.
+#[derive(Default, Clone)]
+pub struct HcaptchaSecret(Zeroizing<String>);
+
+impl fmt::Debug for HcaptchaSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HcaptchaSecret").field(&REDACTED).finish()
+    }
+}
 
 impl fmt::Display for HcaptchaSecret {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        f.write_str(REDACTED)
+    }
+}
+
+impl serde::Serialize for HcaptchaSecret {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.expose())
     }
 }
 
 impl HcaptchaSecret {
+    /// Get the value of the secret.
+    pub(crate) fn expose(&self) -> &str {
+        &self.0
+    }
+
     #[cfg_attr(
         feature = "trace",
         tracing::instrument(name = "Simple check of secret.", skip(s), level = "debug")
     )]
     pub fn parse(s: String) -> Result<Self, HcaptchaError> {
+        let s = Zeroizing::new(s);
         if s.trim().is_empty() {
             let mut codes = HashSet::new();
             codes.insert(Code::MissingSecret);
@@ -37,6 +64,19 @@ mod tests {
     use crate::HcaptchaError;
     use claims::{assert_err, assert_ok};
 
+    #[test]
+    fn secret_is_redacted() {
+        let value = "0x0000000123456789abcdefABCDEF000000000000";
+        let secret = HcaptchaSecret::parse(value.to_string()).unwrap();
+        assert!(!format!("{:?}", secret).contains(value));
+        assert!(!secret.to_string().contains(value));
+        assert_eq!(secret.expose(), value);
+        assert_eq!(
+            serde_json::to_string(&secret).unwrap(),
+            format!("\"{}\"", value)
+        );
+    }
+
     #[test]
     fn whitespace_only_secrets_are_rejected() {
         let secret = " ".to_string();
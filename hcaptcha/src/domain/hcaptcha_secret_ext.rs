@@ -1,25 +1,50 @@
 use crate::{Code, HcaptchaError};
 use std::collections::HashSet;
 use std::fmt;
+use zeroize::Zeroizing;
 
 const SECRET_LEN_V1: usize = 42;
 const SECRET_LEN_V2: usize = 35;
 
-#[derive(Debug, Default, Clone, serde::Serialize)]
-pub struct HcaptchaSecret(String);
+/// Text shown in place of the secret.
+const REDACTED: &str = "[REDACTED]";
+
+/// The secret is zeroed when dropped and redacted when formatted. It is only
+/// exposed to build the form sent to the API.
+#[derive(Default, Clone)]
+pub struct HcaptchaSecret(Zeroizing<String>);
+
+impl fmt::Debug for HcaptchaSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("HcaptchaSecret").field(&REDACTED).finish()
+    }
+}
 
 impl fmt::Display for HcaptchaSecret {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.0)
+        f.write_str(REDACTED)
+    }
+}
+
+impl serde::Serialize for HcaptchaSecret {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.expose())
     }
 }
 
 impl HcaptchaSecret {
+    /// Get the value of the secret.
+    pub(crate) fn expose(&self) -> &str {
+        &self.0
+    }
+
     #[cfg_attr(
         feature = "trace",
         tracing::instrument(name = "Extended check of secret.", skip(s), level = "debug")
     )]
-
     pub fn parse(s: String) -> Result<Self, HcaptchaError> {
         match SecretVersions::parse(s)? {
             SecretVersions::V1(s) => HcaptchaSecret::parse_v1(s),
@@ -28,6 +53,7 @@ impl HcaptchaSecret {
     }
 
     pub fn parse_v1(s: String) -> Result<Self, HcaptchaError> {
+        let s = Zeroizing::new(s);
         let is_wrong_length = s.len() != SECRET_LEN_V1;
         let is_not_a_hex_string = !is_hex_string(&s);
         let mut codes = HashSet::new();
@@ -47,8 +73,9 @@ impl HcaptchaSecret {
     }
 
     pub fn parse_v2(s: String) -> Result<Self, HcaptchaError> {
+        let s = Zeroizing::new(s);
         let is_wrong_length = s.len() != SECRET_LEN_V2;
-        let hex_portion = s.replace("ES_", "0x");
+        let hex_portion = Zeroizing::new(s.replace("ES_", "0x"));
         let is_not_a_hex_string = !is_hex_string(&hex_portion);
         let mut codes = HashSet::new();
         if is_wrong_length {
@@ -73,7 +100,9 @@ impl HcaptchaSecret {
 )]
 fn is_hex_string(s: &str) -> bool {
     let start_is_valid = &s[0..2] == "0x";
-    let string_is_valid = hex::decode(s.trim_start_matches("0x")).is_ok();
+    let string_is_valid = hex::decode(s.trim_start_matches("0x"))
+        .map(Zeroizing::new)
+        .is_ok();
 
     start_is_valid && string_is_valid
 }
@@ -115,6 +144,19 @@ mod tests {
     use crate::HcaptchaError;
     use claims::{assert_err, assert_ok};
 
+    #[test]
+    fn secret_is_redacted() {
+        let value = "0x0000000123456789abcdefABCDEF000000000000";
+        let secret = HcaptchaSecret::parse(value.to_string()).unwrap();
+        assert!(!format!("{:?}", secret).contains(value));
+        assert!(!secret.to_string().contains(value));
+        assert_eq!(secret.expose(), value);
+        assert_eq!(
+            serde_json::to_string(&secret).unwrap(),
+            format!("\"{}\"", value)
+        );
+    }
+
     #[test]
     fn whitespace_only_secrets_are_rejected() {
         let secret = " ".to_string();
@@ -1,14 +1,90 @@
 //! Hcaptcha Trait
-use crate::{HcaptchaError, HcaptchaResponse};
+use crate::{HcaptchaClient, HcaptchaError, HcaptchaResponse};
 // use async_trait::async_trait;
 use std::{future::Future, pin::Pin};
 
 /// Hcaptcha trait
 pub trait Hcaptcha {
     /// valid response function
+    ///
+    /// A new [HcaptchaClient] is created for each call. Use
+    /// [valid_response_with_client](Hcaptcha::valid_response_with_client)
+    /// to reuse an existing client.
+    ///
+    /// The derived implementation builds the client with the default
+    /// reqwest transport. Without the `reqwest` feature it returns an error.
     fn valid_response(
         &self,
         secret: &str,
         uri: Option<String>,
     ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>>;
+
+    /// valid response function using an existing client
+    ///
+    /// The client is cloned into the returned future. Clones share the
+    /// transport, and connection pool, of the original client.
+    ///
+    /// The default implementation calls
+    /// [valid_response](Hcaptcha::valid_response) with the url of the
+    /// client, so implementations written before this method was added keep
+    /// working. Such an implementation creates a new client for each call;
+    /// override this method to reuse the client.
+    fn valid_response_with_client(
+        &self,
+        secret: &str,
+        client: &HcaptchaClient,
+    ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>> {
+        self.valid_response(secret, Some(client.url.to_string()))
+    }
+
+    /// valid response function using an existing client and the address of
+    /// the connection
+    ///
+    /// The remoteip fills in for a missing or empty `#[remoteip]` field of a
+    /// derived struct. Server framework integrations call this method with
+    /// the address of the connection.
+    ///
+    /// The default implementation ignores the remoteip.
+    fn valid_response_with_remoteip(
+        &self,
+        secret: &str,
+        client: &HcaptchaClient,
+        remoteip: Option<&str>,
+    ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>> {
+        let _ = remoteip;
+        self.valid_response_with_client(secret, client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::InMemoryTransport;
+
+    struct Form;
+
+    impl Hcaptcha for Form {
+        fn valid_response(
+            &self,
+            _secret: &str,
+            uri: Option<String>,
+        ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>> {
+            Box::pin(async move { Err(HcaptchaError::Transport(uri.unwrap_or_default().into())) })
+        }
+    }
+
+    #[tokio::test]
+    async fn valid_response_with_client_defaults_to_valid_response() {
+        let client = HcaptchaClient::with_transport(InMemoryTransport::new())
+            .set_url("https://domain.com/siteverify")
+            .unwrap();
+
+        let response = Form
+            .valid_response_with_client("0x123456789abcde0f123456789abcdef012345678", &client)
+            .await;
+        assert_eq!(
+            response.unwrap_err().to_string(),
+            "Transport error: https://domain.com/siteverify"
+        );
+    }
 }
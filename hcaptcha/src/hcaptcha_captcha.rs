@@ -365,7 +365,7 @@ impl HcaptchaCaptcha {
         tracing::instrument(name = "Get remoteip field.", level = "debug")
     )]
     pub fn remoteip(&self) -> Option<HcaptchaRemoteip> {
-        self.remoteip.clone()
+        self.remoteip
     }
 
     /// Get the value of the sitekey field.
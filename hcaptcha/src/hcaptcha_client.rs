@@ -8,6 +8,10 @@
 //! A default url is stored in the const VERIFY_URL.
 //! The new_with method allows the specification of an alternative url.
 //!
+//! Requests are sent through a [HcaptchaTransport]. By default a reqwest
+//! client is used. Use [HcaptchaClient::with_transport] or
+//! [HcaptchaClientBuilder::transport] to provide another implementation.
+//!
 //! # Examples
 //! Create client to connect to default API endpoint.
 //! ```
@@ -15,7 +19,26 @@
 //!     let client = HcaptchaClient::new();
 //! ```
 //!
+//! Create a client with a timeout for each request and an overall deadline
+//! for the verification.
+//! ```
+//! # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::HcaptchaClient;
+//!     use std::time::Duration;
+//!
+//!     let client = HcaptchaClient::builder()
+//!         .timeout(Duration::from_secs(2))
+//!         .deadline(Duration::from_secs(5))
+//!         .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! Create a client and submit for verification.
+//!
+//! Verification borrows the client, so a single client (and its
+//! connection pool) can be reused for any number of requests. The client is
+//! cheap to clone and clones share the same connection pool.
 //!```no_run
 //!     use hcaptcha::{HcaptchaCaptcha, HcaptchaClient, HcaptchaRequest};
 //!
@@ -57,28 +80,97 @@
 // const CYAN: &str = "\u{001b}[35m";
 // const RESET: &str = "\u{001b}[0m";
 
+use crate::Code;
+use crate::HcaptchaCaptcha;
 use crate::HcaptchaError;
 use crate::HcaptchaRequest;
 use crate::HcaptchaResponse;
-use reqwest::{Client, Url};
+use crate::HcaptchaTransport;
+#[cfg(feature = "reqwest")]
+use crate::ReqwestTransport;
+use crate::TransportResponse;
+use futures_util::stream::{self, Stream, StreamExt};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+use zeroize::Zeroizing;
 
+mod circuit_breaker;
+mod hcaptcha_client_builder;
 mod hcaptcha_form;
+mod rate_limit;
+mod remoteip_privacy;
+mod replay_guard;
+mod retry_policy;
+#[cfg(feature = "enterprise")]
+mod score_policy;
+mod secret_provider;
+mod secret_registry;
+mod verification_cache;
+mod verification_policy;
 
+use circuit_breaker::Breaker;
+pub use circuit_breaker::{CircuitBreaker, OpenPolicy};
+pub use hcaptcha_client_builder::HcaptchaClientBuilder;
 use hcaptcha_form::HcaptchaForm;
+use rate_limit::Limiter;
+pub use rate_limit::{Overflow, RateLimit};
+pub use remoteip_privacy::RemoteipPrivacy;
+pub use replay_guard::{InMemoryReplayGuard, ReplayGuard, TokenHash};
+pub use retry_policy::RetryPolicy;
+#[cfg(feature = "enterprise")]
+pub use score_policy::{ScorePolicy, Verdict};
+pub use secret_provider::{CachedSecretProvider, SecretProvider, Secrets};
+pub use secret_registry::SecretRegistry;
+pub use verification_cache::VerificationCache;
+pub use verification_policy::VerificationPolicy;
 
 /// Endpoint url for the Hcaptcha siteverify API.
 pub const VERIFY_URL: &str = "https://hcaptcha.com/siteverify";
 
+/// Number of characters of the body reported with an unexpected HTTP status.
+const BODY_EXCERPT_LEN: usize = 256;
+
 /// Client to submit a request to a Hcaptcha validation endpoint.
+///
+/// The client can be reused across requests and shared between tasks, either
+/// by cloning it or by wrapping it in an [`Arc`](std::sync::Arc). Clones share
+/// the same [HcaptchaTransport] and underlying connection pool.
 #[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HcaptchaClient {
-    /// HTTP Client to submit request to endpoint and read the response.
-    client: Client,
+    /// Transport to submit request to endpoint and read the response.
+    transport: Arc<dyn HcaptchaTransport>,
     /// Url for the endpoint.
-    url: Url,
+    pub(crate) url: Url,
+    /// Overall time allowed for a verification to complete.
+    deadline: Option<Duration>,
+    /// Policy to retry transient failures.
+    retry: Option<RetryPolicy>,
+    /// Policy checked against a successful response.
+    policy: Option<VerificationPolicy>,
+    /// Policy deciding the verdict from the score of the response.
+    #[cfg(feature = "enterprise")]
+    score_policy: Option<ScorePolicy>,
+    /// How much of the remoteip is sent to the API.
+    remoteip_privacy: RemoteipPrivacy,
+    /// Secrets of the sites verified by the client.
+    secrets: Option<SecretRegistry>,
+    /// Provider of the secret used if no registry is set.
+    secret_provider: Option<Arc<dyn SecretProvider>>,
+    /// Record of the tokens sent to the API.
+    replay_guard: Option<Arc<dyn ReplayGuard>>,
+    /// Cache of successful verifications.
+    cache: Option<VerificationCache>,
+    /// Limit on the calls to the API.
+    limiter: Option<Arc<Limiter>>,
+    /// Circuit breaker on the calls to the API.
+    breaker: Option<Arc<Breaker>>,
 }
 
+#[cfg(feature = "reqwest")]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
 #[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
 impl Default for HcaptchaClient {
     fn default() -> HcaptchaClient {
@@ -101,15 +193,69 @@ impl HcaptchaClient {
     ///
     /// If the default API url constant is corrupted the function with
     /// will panic.
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
     #[allow(unknown_lints)]
     #[cfg_attr(docsrs, allow(rustdoc::bare_urls))]
     pub fn new() -> HcaptchaClient {
+        HcaptchaClient::with_transport(ReqwestTransport::new())
+    }
+
+    /// Create a new Hcaptcha Client using the transport to connect with the
+    /// default Hcaptcha siteverify API endpoint specified in [VERIFY_URL].
+    ///
+    /// # Example
+    /// Initialise a client with an in memory transport for testing.
+    /// ```
+    ///     use hcaptcha::{HcaptchaClient, InMemoryTransport};
+    ///     let client = HcaptchaClient::with_transport(InMemoryTransport::new());
+    /// ```
+    /// # Panic
+    ///
+    /// If the default API url constant is corrupted the function with
+    /// will panic.
+    pub fn with_transport<T>(transport: T) -> HcaptchaClient
+    where
+        T: HcaptchaTransport + 'static,
+    {
         HcaptchaClient {
-            client: Client::new(),
+            transport: Arc::new(transport),
             url: Url::parse(VERIFY_URL).expect("API url string corrupt"),
+            deadline: None,
+            retry: None,
+            policy: None,
+            #[cfg(feature = "enterprise")]
+            score_policy: None,
+            remoteip_privacy: RemoteipPrivacy::default(),
+            secrets: None,
+            secret_provider: None,
+            replay_guard: None,
+            cache: None,
+            limiter: None,
+            breaker: None,
         }
     }
 
+    /// Create a [HcaptchaClientBuilder] to configure timeouts, the
+    /// verification deadline and connection settings for the client.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+    ///     use hcaptcha::HcaptchaClient;
+    ///     use std::time::Duration;
+    ///
+    ///     let client = HcaptchaClient::builder()
+    ///         .connect_timeout(Duration::from_secs(1))
+    ///         .deadline(Duration::from_secs(5))
+    ///         .build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder() -> HcaptchaClientBuilder {
+        HcaptchaClientBuilder::new()
+    }
+
     /// Create a new Hcaptcha Client and specify the url for the API.
     ///
     /// Specify the url for the hcaptcha API.
@@ -123,10 +269,12 @@ impl HcaptchaClient {
     ///     let url = "https://domain.com/siteverify";
     ///     let _client = HcaptchaClient::new_with(url);
     /// ```
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
     pub fn new_with(url: &str) -> Result<HcaptchaClient, url::ParseError> {
         Ok(HcaptchaClient {
-            client: Client::new(),
             url: Url::parse(url)?,
+            ..HcaptchaClient::new()
         })
     }
 
@@ -168,6 +316,35 @@ impl HcaptchaClient {
     /// This method returns [HcaptchaResponse] if successful and [HcaptchaError] if
     /// unsuccessful.
     ///
+    /// The client is borrowed for the call and remains available to verify
+    /// further requests.
+    ///
+    /// If a request timeout or deadline was set with [HcaptchaClientBuilder]
+    /// and is exceeded [HcaptchaError::Timeout] is returned.
+    ///
+    /// If a [RetryPolicy] was set with [HcaptchaClientBuilder] transient
+    /// failures are retried. The deadline applies across all attempts.
+    ///
+    /// A response from the API with an HTTP status other than success is
+    /// reported as [HcaptchaError::HttpStatus]. A body that cannot be decoded,
+    /// such as an HTML error page from a proxy, is reported as
+    /// [HcaptchaError::Decode].
+    ///
+    /// If a [ReplayGuard] was set with [HcaptchaClientBuilder] a token it
+    /// has already recorded is rejected with [Code::InvalidAlreadySeen]
+    /// without calling the API.
+    ///
+    /// If a [VerificationCache] was set with [HcaptchaClientBuilder] the
+    /// response to a successful verification is returned again for the same
+    /// token and secret while it is cached, without calling the API.
+    ///
+    /// If a [RateLimit] was set with [HcaptchaClientBuilder] and the
+    /// verification exceeds it, [HcaptchaError::RateLimited] is returned.
+    ///
+    /// If a [CircuitBreaker] was set with [HcaptchaClientBuilder] and the
+    /// circuit is open, the API is not called and the verification is
+    /// decided by the [OpenPolicy].
+    ///
     /// # Example
     ///
     ///
@@ -231,34 +408,347 @@ impl HcaptchaClient {
         )
     )]
     pub async fn verify_client_response(
-        self,
+        &self,
         request: HcaptchaRequest,
     ) -> Result<HcaptchaResponse, HcaptchaError> {
-        let form: HcaptchaForm = request.into();
+        let captcha = self.breaker.as_ref().map(|_| request.captcha());
+        let mut form: HcaptchaForm = request.into();
+        if let Some(policy) = &self.policy {
+            policy.bind_sitekey(&mut form)?;
+        }
+        self.remoteip_privacy.apply(&mut form);
         #[cfg(feature = "trace")]
-        tracing::debug!(
-            "The form to submit to Hcaptcha API: {:?}",
-            serde_urlencoded::to_string(&form).unwrap_or_else(|_| "form corrupted".to_owned())
-        );
-        let response = self
-            .client
-            .post(self.url.clone())
-            .form(&form)
-            .send()
-            .await?
-            .json::<HcaptchaResponse>()
-            .await?;
+        tracing::debug!("Remoteip privacy: {:?}", self.remoteip_privacy);
+        let cached = self.cache.as_ref().map(|cache| {
+            let key = TokenHash::with_secret(form.response(), form.secret().expose());
+            (cache, key)
+        });
+        if let Some(response) = cached.as_ref().and_then(|(cache, key)| cache.get(key)) {
+            #[cfg(feature = "trace")]
+            tracing::debug!("The response is cached: {:?}", response);
+            // The response may no longer comply, such as with the maximum age.
+            if let Some(policy) = &self.policy {
+                policy.check(&response)?;
+            }
+            return Ok(response);
+        }
+        let _permit = match &self.limiter {
+            Some(limiter) => limiter.acquire().await?,
+            None => None,
+        };
+        let token = match &self.replay_guard {
+            Some(guard) => Some((guard, self.record_token(guard, &form).await?)),
+            None => None,
+        };
+        // Ask the breaker last, so the trial call of a half open circuit is
+        // only taken by a request that is sent.
+        if let (Some(breaker), Some(captcha)) = (&self.breaker, &captcha) {
+            if !breaker.allow() {
+                if let Some((guard, token)) = token {
+                    self.forget_token(guard, &token).await;
+                }
+                return breaker.open(captcha);
+            }
+        }
+        #[cfg(feature = "trace")]
+        tracing::debug!("The form to submit to Hcaptcha API: {:?}", form.redacted());
+        let sent = match self.deadline {
+            Some(deadline) => tokio::time::timeout(deadline, self.send_form(&form))
+                .await
+                .unwrap_or(Err(HcaptchaError::Timeout(deadline))),
+            None => self.send_form(&form).await,
+        };
+        let unused = match &sent {
+            // The request did not reach the API.
+            Err(e) => e.is_connect(),
+            // The API rejected the secret without checking the token.
+            Ok(response) => response
+                .error_codes()
+                .is_some_and(|codes| codes.contains(&Code::InvalidSecret)),
+        };
+        if let (true, Some((guard, token))) = (unused, token) {
+            self.forget_token(guard, &token).await;
+        }
+        if let Some(breaker) = &self.breaker {
+            breaker.record(sent.is_ok());
+        }
+        let response = sent?;
         #[cfg(feature = "trace")]
         tracing::debug!("The response is: {:?}", response);
         response.check_error()?;
+        if let Some(policy) = &self.policy {
+            policy.check(&response)?;
+        }
+        if let Some((cache, key)) = cached {
+            cache.insert(key, response.clone());
+        }
         Ok(response)
     }
+
+    /// Verify the captcha with the secret registered for its sitekey or
+    /// supplied by the secret provider.
+    ///
+    /// The secret is taken from the [SecretRegistry] set on the
+    /// [HcaptchaClientBuilder]. If no registry is set the [SecretProvider]
+    /// is consulted. When the provider supplies a secondary secret and the
+    /// API rejects the primary secret with [Code::InvalidSecret], the
+    /// verification is repeated once with the secondary secret.
+    ///
+    /// # Errors
+    ///
+    /// An [HcaptchaError::Codes] containing [Code::MissingSecret] if neither
+    /// a registry nor a provider is set. With a registry, [Code::MissingSiteKey]
+    /// if the captcha has no sitekey, or [Code::UnknownSitekey] if no secret
+    /// is registered for the sitekey. The errors of the provider are
+    /// returned as is. Otherwise the errors are those of
+    /// [verify_client_response](HcaptchaClient::verify_client_response).
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Request verification with registered secret.",
+            skip(self),
+            level = "debug"
+        )
+    )]
+    pub async fn verify_captcha(
+        &self,
+        captcha: HcaptchaCaptcha,
+    ) -> Result<HcaptchaResponse, HcaptchaError> {
+        if let Some(registry) = &self.secrets {
+            let sitekey = captcha
+                .sitekey()
+                .ok_or_else(|| code_error(Code::MissingSiteKey))?;
+            let secret = registry.secret(&sitekey.to_string())?;
+            return self
+                .verify_client_response(HcaptchaRequest::with_secret(secret, captcha))
+                .await;
+        }
+
+        let provider = self
+            .secret_provider
+            .as_ref()
+            .ok_or_else(|| code_error(Code::MissingSecret))?;
+        let (primary, secondary) = provider.secrets().await?.into_parts();
+        let Some(secondary) = secondary else {
+            return self
+                .verify_client_response(HcaptchaRequest::with_secret(primary, captcha))
+                .await;
+        };
+        match self
+            .verify_client_response(HcaptchaRequest::with_secret(primary, captcha.clone()))
+            .await
+        {
+            Err(HcaptchaError::Codes(codes)) if codes.contains(&Code::InvalidSecret) => {
+                #[cfg(feature = "trace")]
+                tracing::debug!("Primary secret rejected, retrying with the secondary secret");
+                self.verify_client_response(HcaptchaRequest::with_secret(secondary, captcha))
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    /// Verify the client token and decide a [Verdict] from the score of the
+    /// response.
+    ///
+    /// The verdict is decided by the [ScorePolicy] set on the
+    /// [HcaptchaClientBuilder], or the default policy if none is set. The
+    /// thresholds for the action apply if the action is given.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), hcaptcha::HcaptchaError> {
+    ///     use hcaptcha::{HcaptchaClient, HcaptchaRequest, ScorePolicy, Verdict};
+    ///
+    /// #   let secret = "0x123456789abcde0f123456789abcdef012345678";
+    /// #   let token = "10000000-aaaa-bbbb-cccc-000000000001";
+    ///     let client = HcaptchaClient::builder()
+    ///         .score_policy(ScorePolicy::new().action_thresholds("login", 0.3, 0.6))
+    ///         .build()?;
+    ///     let request = HcaptchaRequest::new_from_response(secret, token)?;
+    ///
+    ///     let (response, verdict) = client
+    ///         .verify_with_verdict(request, Some("login"))
+    ///         .await?;
+    ///     if verdict == Verdict::Challenge {
+    ///         // ask for a second factor
+    ///     }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// The errors are those of [verify_client_response](HcaptchaClient::verify_client_response).
+    #[cfg(feature = "enterprise")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+    pub async fn verify_with_verdict(
+        &self,
+        request: HcaptchaRequest,
+        action: Option<&str>,
+    ) -> Result<(HcaptchaResponse, Verdict), HcaptchaError> {
+        let response = self.verify_client_response(request).await?;
+        let verdict = match &self.score_policy {
+            Some(policy) => policy.evaluate(&response, action),
+            None => ScorePolicy::default().evaluate(&response, action),
+        };
+        Ok((response, verdict))
+    }
+
+    /// Record the token of the form with the replay guard, rejecting a token
+    /// that is already recorded.
+    async fn record_token(
+        &self,
+        guard: &Arc<dyn ReplayGuard>,
+        form: &HcaptchaForm,
+    ) -> Result<TokenHash, HcaptchaError> {
+        let token = TokenHash::new(form.response());
+        if guard.record(&token).await? {
+            Ok(token)
+        } else {
+            #[cfg(feature = "trace")]
+            tracing::debug!("The token has already been recorded by the replay guard");
+            Err(code_error(Code::InvalidAlreadySeen))
+        }
+    }
+
+    /// Forget a token the API has not used, so it may be submitted again.
+    async fn forget_token(&self, guard: &Arc<dyn ReplayGuard>, token: &TokenHash) {
+        if let Err(_e) = guard.forget(token).await {
+            #[cfg(feature = "trace")]
+            tracing::debug!("The replay guard failed to forget the token: {}", _e);
+        }
+    }
+
+    /// Verify many requests concurrently and return the results in the order
+    /// of the requests.
+    ///
+    /// At most `concurrency` verifications are in progress at a time. Each
+    /// request has its own result, so a failed verification does not stop
+    /// the others. The limits set on the client, such as a [RateLimit], apply
+    /// to each verification.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), hcaptcha::HcaptchaError> {
+    ///     use hcaptcha::{HcaptchaClient, HcaptchaRequest};
+    ///
+    /// #   let secret = "0x123456789abcde0f123456789abcdef012345678";
+    /// #   let tokens = vec!["10000000-aaaa-bbbb-cccc-000000000001"];
+    ///     let requests = tokens
+    ///         .iter()
+    ///         .map(|token| HcaptchaRequest::new_from_response(secret, token))
+    ///         .collect::<Result<Vec<_>, _>>()?;
+    ///
+    ///     let client = HcaptchaClient::new();
+    ///     for (token, result) in tokens.iter().zip(client.verify_many(requests, 8).await) {
+    ///         match result {
+    ///             Ok(response) => println!("{}: {}", token, response.success()),
+    ///             Err(e) => println!("{}: {}", token, e),
+    ///         }
+    ///     }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn verify_many<I>(
+        &self,
+        requests: I,
+        concurrency: usize,
+    ) -> Vec<Result<HcaptchaResponse, HcaptchaError>>
+    where
+        I: IntoIterator<Item = HcaptchaRequest>,
+    {
+        self.verify_stream(requests, concurrency).collect().await
+    }
+
+    /// Verify many requests concurrently and stream the results in the order
+    /// of the requests.
+    ///
+    /// The stream form of [verify_many](HcaptchaClient::verify_many), to
+    /// process each result as it becomes available.
+    pub fn verify_stream<'a, I>(
+        &'a self,
+        requests: I,
+        concurrency: usize,
+    ) -> impl Stream<Item = Result<HcaptchaResponse, HcaptchaError>> + 'a
+    where
+        I: IntoIterator<Item = HcaptchaRequest>,
+        I::IntoIter: 'a,
+    {
+        stream::iter(requests)
+            .map(move |request| self.verify_client_response(request))
+            .buffered(concurrency.max(1))
+    }
+
+    /// Post the form to the API and decode the response, retrying transient
+    /// failures if a [RetryPolicy] is set.
+    async fn send_form(&self, form: &HcaptchaForm) -> Result<HcaptchaResponse, HcaptchaError> {
+        let form = Zeroizing::new(serde_urlencoded::to_string(form)?);
+        let mut attempt = 1;
+        loop {
+            let retry = self.retry.as_ref().filter(|p| p.allows_retry(attempt));
+            let delay = match self.transport.post_form(&self.url, &form).await {
+                Ok(response) => match retry.and_then(|p| {
+                    p.status_delay(attempt, response.status(), response.retry_after_header())
+                }) {
+                    Some(delay) => delay,
+                    None => {
+                        let response = decode(response)?;
+                        match retry.and_then(|p| p.codes_delay(attempt, &response)) {
+                            Some(delay) => delay,
+                            None => return Ok(response),
+                        }
+                    }
+                },
+                Err(e) => match retry.and_then(|p| p.error_delay(attempt, &e)) {
+                    Some(delay) => delay,
+                    None => return Err(e),
+                },
+            };
+            #[cfg(feature = "trace")]
+            tracing::debug!("Attempt {} failed, retrying in {:?}", attempt, delay);
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+fn decode(response: TransportResponse) -> Result<HcaptchaResponse, HcaptchaError> {
+    if !response.is_success() {
+        #[cfg(feature = "trace")]
+        tracing::debug!("The API returned status {}", response.status());
+        return Err(HcaptchaError::HttpStatus {
+            status: response.status(),
+            body_excerpt: body_excerpt(response.body()),
+        });
+    }
+
+    serde_json::from_slice(response.body()).map_err(|source| HcaptchaError::Decode {
+        content_type: response.content_type_header().map(|v| v.to_owned()),
+        source,
+    })
+}
+
+fn body_excerpt(body: &[u8]) -> String {
+    String::from_utf8_lossy(body)
+        .chars()
+        .take(BODY_EXCERPT_LEN)
+        .collect()
+}
+
+/// Error reporting the code.
+fn code_error(code: Code) -> HcaptchaError {
+    let mut codes = HashSet::new();
+    codes.insert(code);
+    HcaptchaError::Codes(codes)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::Code;
+    #[cfg(feature = "reqwest")]
     use chrono::{TimeDelta, Utc};
     use claims::assert_ok;
     use rand::distributions::Alphanumeric;
@@ -267,7 +757,9 @@ mod tests {
     use std::iter;
     #[cfg(feature = "trace")]
     use tracing_test::traced_test;
+    #[cfg(feature = "reqwest")]
     use wiremock::matchers::{body_string, method, path};
+    #[cfg(feature = "reqwest")]
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     // const RED: &str = "\t\u{001b}[31m";
@@ -284,6 +776,7 @@ mod tests {
             .collect()
     }
 
+    #[cfg(feature = "reqwest")]
     #[tokio::test]
     #[cfg_attr(feature = "trace", traced_test)]
     async fn hcaptcha_mock() {
@@ -321,9 +814,14 @@ mod tests {
         #[cfg(feature = "trace")]
         assert!(logs_contain("Hcaptcha API"));
         #[cfg(feature = "trace")]
+        assert!(!logs_contain(&secret));
+        #[cfg(feature = "trace")]
+        assert!(!logs_contain(&token));
+        #[cfg(feature = "trace")]
         assert!(logs_contain("The response is"));
     }
 
+    #[cfg(feature = "reqwest")]
     #[tokio::test]
     #[cfg_attr(feature = "trace", traced_test)]
     async fn hcaptcha_mock_with_remoteip() {
@@ -371,6 +869,7 @@ mod tests {
         assert!(logs_contain("The response is"));
     }
 
+    #[cfg(feature = "reqwest")]
     #[tokio::test]
     #[cfg_attr(feature = "trace", traced_test)]
     async fn hcaptcha_mock_with_sitekey() {
@@ -418,6 +917,939 @@ mod tests {
         assert!(logs_contain("The response is"));
     }
 
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn hcaptcha_mock_client_is_reused() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let timestamp = Utc::now()
+            .checked_sub_signed(TimeDelta::try_minutes(10).unwrap())
+            .unwrap()
+            .to_rfc3339();
+
+        let response_template = ResponseTemplate::new(200).set_body_json(json!({
+            "success": true,
+            "challenge_ts": timestamp,
+            "hostname": "test-host",
+        }));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = std::sync::Arc::new(HcaptchaClient::new_with(&uri).unwrap());
+
+        let request = HcaptchaRequest::new_from_response(&secret, &random_string(100)).unwrap();
+        assert_ok!(client.verify_client_response(request).await);
+        let request = HcaptchaRequest::new_from_response(&secret, &random_string(100)).unwrap();
+        assert_ok!(client.verify_client_response(request).await);
+
+        let cloned = client.as_ref().clone();
+        let request = HcaptchaRequest::new_from_response(&secret, &random_string(100)).unwrap();
+        let handle = tokio::spawn(async move { cloned.verify_client_response(request).await });
+        assert_ok!(handle.await.unwrap());
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn hcaptcha_mock_request_timeout() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+
+        let response_template = ResponseTemplate::new(200)
+            .set_body_json(json!({ "success": true }))
+            .set_delay(std::time::Duration::from_millis(500));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = HcaptchaClient::builder()
+            .url(&uri)
+            .timeout(std::time::Duration::from_millis(50))
+            .connect_timeout(std::time::Duration::from_secs(10))
+            .build()
+            .unwrap();
+        let response = client.verify_client_response(request).await;
+        assert!(matches!(
+            response,
+            Err(HcaptchaError::Timeout(d)) if d == std::time::Duration::from_millis(50)
+        ));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn hcaptcha_mock_deadline_exceeded() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+
+        let response_template = ResponseTemplate::new(200)
+            .set_body_json(json!({ "success": true }))
+            .set_delay(std::time::Duration::from_millis(500));
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(response_template)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = HcaptchaClient::builder()
+            .url(&uri)
+            .deadline(std::time::Duration::from_millis(50))
+            .build()
+            .unwrap();
+        let response = client.verify_client_response(request).await;
+        assert!(matches!(
+            response,
+            Err(HcaptchaError::Timeout(d)) if d == std::time::Duration::from_millis(50)
+        ));
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn hcaptcha_mock_retries_service_unavailable() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(ResponseTemplate::new(503).insert_header("Retry-After", "0"))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+                "hostname": "test-host",
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = HcaptchaClient::builder()
+            .url(&uri)
+            .retry(RetryPolicy::new().initial_backoff(std::time::Duration::from_millis(1)))
+            .build()
+            .unwrap();
+        let response = client.verify_client_response(request).await;
+        assert_ok!(&response);
+        assert!(response.unwrap().success());
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn hcaptcha_mock_retries_are_limited() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(ResponseTemplate::new(429))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = HcaptchaClient::builder()
+            .url(&uri)
+            .retry(
+                RetryPolicy::new()
+                    .max_attempts(2)
+                    .initial_backoff(std::time::Duration::from_millis(1)),
+            )
+            .build()
+            .unwrap();
+        assert!(client.verify_client_response(request).await.is_err());
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn hcaptcha_mock_already_seen_is_not_retried() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": false,
+                "error-codes": ["invalid-or-already-seen-response"],
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = HcaptchaClient::builder()
+            .url(&uri)
+            .retry(
+                RetryPolicy::new()
+                    .retry_on_code(Code::InvalidAlreadySeen)
+                    .initial_backoff(std::time::Duration::from_millis(1)),
+            )
+            .build()
+            .unwrap();
+        let response = client.verify_client_response(request).await;
+        match response {
+            Err(HcaptchaError::Codes(codes)) => {
+                assert!(codes.contains(&Code::InvalidAlreadySeen))
+            }
+            _ => panic!("expected error codes"),
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn hcaptcha_mock_retries_configured_code() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": false,
+                "error-codes": ["internal-error"],
+            })))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "success": true,
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = HcaptchaClient::builder()
+            .url(&uri)
+            .retry(
+                RetryPolicy::new()
+                    .retry_on_code(Code::Unknown("internal-error".to_owned()))
+                    .initial_backoff(std::time::Duration::from_millis(1)),
+            )
+            .build()
+            .unwrap();
+        assert_ok!(client.verify_client_response(request).await);
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn hcaptcha_mock_bad_gateway_reports_status() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(
+                ResponseTemplate::new(502)
+                    .set_body_raw("<html><body>502 Bad Gateway</body></html>", "text/html"),
+            )
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = HcaptchaClient::new_with(&uri).unwrap();
+        match client.verify_client_response(request).await {
+            Err(HcaptchaError::HttpStatus {
+                status,
+                body_excerpt,
+            }) => {
+                assert_eq!(status, 502);
+                assert!(body_excerpt.contains("502 Bad Gateway"));
+            }
+            r => panic!("expected http status error, got {:?}", r),
+        }
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[tokio::test]
+    async fn hcaptcha_mock_html_body_reports_content_type() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/siteverify"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw("<html>maintenance</html>", "text/html"),
+            )
+            .mount(&mock_server)
+            .await;
+        let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+
+        let client = HcaptchaClient::new_with(&uri).unwrap();
+        match client.verify_client_response(request).await {
+            Err(HcaptchaError::Decode { content_type, .. }) => {
+                assert_eq!(content_type.as_deref(), Some("text/html"));
+            }
+            r => panic!("expected decode error, got {:?}", r),
+        }
+    }
+
+    #[tokio::test]
+    async fn in_memory_transport_is_used() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+
+        let transport = crate::InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(503, "").retry_after("0"));
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": true }).to_string(),
+        ));
+
+        let client = HcaptchaClient::builder()
+            .transport(transport.clone())
+            .retry(RetryPolicy::new())
+            .build()
+            .unwrap();
+        assert_ok!(client.verify_client_response(request).await);
+
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].0.as_str(), VERIFY_URL);
+        assert_eq!(
+            requests[0].1,
+            format!("response={}&secret={}", &token, &secret)
+        );
+    }
+
+    #[tokio::test]
+    async fn policy_binds_sitekey_and_checks_hostname() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let sitekey = mockd::unique::uuid_v4();
+
+        let transport = crate::InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": true, "hostname": "evil.com" }).to_string(),
+        ));
+        let client = HcaptchaClient::builder()
+            .transport(transport.clone())
+            .policy(
+                VerificationPolicy::new()
+                    .allow_hostname("*.example.com")
+                    .sitekey(&sitekey),
+            )
+            .build()
+            .unwrap();
+
+        let request = HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+        match client.verify_client_response(request).await {
+            Err(HcaptchaError::Codes(codes)) => assert!(codes.contains(&Code::HostnameMismatch)),
+            r => panic!("expected error codes, got {:?}", r),
+        }
+        assert_eq!(
+            transport.requests()[0].1,
+            format!(
+                "response={}&sitekey={}&secret={}",
+                &token, &sitekey, &secret
+            )
+        );
+
+        let request = HcaptchaRequest::new_from_response(&secret, &token)
+            .unwrap()
+            .set_sitekey(&mockd::unique::uuid_v4())
+            .unwrap();
+        match client.verify_client_response(request).await {
+            Err(HcaptchaError::Codes(codes)) => assert!(codes.contains(&Code::SitekeyMismatch)),
+            r => panic!("expected error codes, got {:?}", r),
+        }
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(feature = "trace", traced_test)]
+    async fn remoteip_privacy_is_applied() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        for (privacy, expected) in [
+            (RemoteipPrivacy::Full, "&remoteip=192.0.2.123"),
+            (RemoteipPrivacy::Truncate, "&remoteip=192.0.2.0"),
+            (RemoteipPrivacy::Withhold, ""),
+        ] {
+            let transport = crate::InMemoryTransport::new();
+            transport.push_response(TransportResponse::new(
+                200,
+                json!({ "success": true }).to_string(),
+            ));
+            let client = HcaptchaClient::builder()
+                .transport(transport.clone())
+                .remoteip_privacy(privacy)
+                .build()
+                .unwrap();
+
+            let request = HcaptchaRequest::new_from_response(&secret, &token)
+                .unwrap()
+                .set_remoteip("192.0.2.123")
+                .unwrap();
+            assert_ok!(client.verify_client_response(request).await);
+            assert_eq!(
+                transport.requests()[0].1,
+                format!("response={}{}&secret={}", &token, expected, &secret)
+            );
+        }
+        #[cfg(feature = "trace")]
+        assert!(logs_contain("Remoteip privacy: Truncate"));
+    }
+
+    #[tokio::test]
+    async fn captcha_is_verified_with_registered_secret() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let sitekey = mockd::unique::uuid_v4();
+
+        let transport = crate::InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": true }).to_string(),
+        ));
+        let client = HcaptchaClient::builder()
+            .transport(transport.clone())
+            .secret_registry(SecretRegistry::new().insert(&sitekey, &secret).unwrap())
+            .build()
+            .unwrap();
+
+        let captcha = HcaptchaCaptcha::new(&token)
+            .unwrap()
+            .set_sitekey(&sitekey)
+            .unwrap();
+        assert_ok!(client.verify_captcha(captcha).await);
+        assert_eq!(
+            transport.requests()[0].1,
+            format!(
+                "response={}&sitekey={}&secret={}",
+                &token, &sitekey, &secret
+            )
+        );
+
+        let captcha = HcaptchaCaptcha::new(&token)
+            .unwrap()
+            .set_sitekey(&mockd::unique::uuid_v4())
+            .unwrap();
+        match client.verify_captcha(captcha).await {
+            Err(HcaptchaError::Codes(codes)) => assert!(codes.contains(&Code::UnknownSitekey)),
+            r => panic!("expected error codes, got {:?}", r),
+        }
+        match client
+            .verify_captcha(HcaptchaCaptcha::new(&token).unwrap())
+            .await
+        {
+            Err(HcaptchaError::Codes(codes)) => assert!(codes.contains(&Code::MissingSiteKey)),
+            r => panic!("expected error codes, got {:?}", r),
+        }
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(feature = "trace", traced_test)]
+    async fn replayed_token_is_rejected_locally() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let transport = crate::InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": true }).to_string(),
+        ));
+        transport.push_response(TransportResponse::new(503, ""));
+        let client = HcaptchaClient::builder()
+            .transport(RefusingTransport::new(transport.clone()))
+            .replay_guard(InMemoryReplayGuard::new(Duration::from_secs(60)))
+            .build()
+            .unwrap();
+
+        // The refused connection does not use the token.
+        let request = || HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+        assert!(matches!(
+            client.verify_client_response(request()).await,
+            Err(HcaptchaError::Io(_))
+        ));
+        assert_ok!(client.verify_client_response(request()).await);
+        match client.verify_client_response(request()).await {
+            Err(HcaptchaError::Codes(codes)) => {
+                assert!(codes.contains(&Code::InvalidAlreadySeen))
+            }
+            r => panic!("expected error codes, got {:?}", r),
+        }
+        assert_eq!(transport.requests().len(), 1);
+        #[cfg(feature = "trace")]
+        assert!(logs_contain("already been recorded"));
+
+        // A server error may have used the token, so it stays recorded.
+        let token = random_string(100);
+        let request = || HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+        assert!(matches!(
+            client.verify_client_response(request()).await,
+            Err(HcaptchaError::HttpStatus { status: 503, .. })
+        ));
+        assert!(matches!(
+            client.verify_client_response(request()).await,
+            Err(HcaptchaError::Codes(_))
+        ));
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    /// Transport refusing the first connection, then answering from the
+    /// in memory transport.
+    #[derive(Debug)]
+    struct RefusingTransport {
+        refused: std::sync::atomic::AtomicBool,
+        transport: crate::InMemoryTransport,
+    }
+
+    impl RefusingTransport {
+        fn new(transport: crate::InMemoryTransport) -> RefusingTransport {
+            RefusingTransport {
+                refused: std::sync::atomic::AtomicBool::new(false),
+                transport,
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl HcaptchaTransport for RefusingTransport {
+        async fn post_form(
+            &self,
+            url: &Url,
+            form: &str,
+        ) -> Result<TransportResponse, HcaptchaError> {
+            if !self.refused.swap(true, std::sync::atomic::Ordering::SeqCst) {
+                return Err(std::io::Error::from(std::io::ErrorKind::ConnectionRefused).into());
+            }
+            self.transport.post_form(url, form).await
+        }
+    }
+
+    #[tokio::test]
+    #[cfg_attr(feature = "trace", traced_test)]
+    async fn cached_response_is_returned_for_same_token() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let transport = crate::InMemoryTransport::new();
+        for _ in 0..2 {
+            transport.push_response(TransportResponse::new(
+                200,
+                json!({ "success": true, "hostname": "example.com" }).to_string(),
+            ));
+        }
+        let cache = VerificationCache::new(Duration::from_secs(60));
+        let client = HcaptchaClient::builder()
+            .transport(transport.clone())
+            .replay_guard(InMemoryReplayGuard::new(Duration::from_secs(60)))
+            .verification_cache(cache.clone())
+            .build()
+            .unwrap();
+
+        let request = || HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+        assert_ok!(client.verify_client_response(request()).await);
+        let response = client.verify_client_response(request()).await.unwrap();
+        assert_eq!(response.hostname().as_deref(), Some("example.com"));
+        assert_eq!(transport.requests().len(), 1);
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        let other_secret = format!("0x{}", hex::encode(random_string(20)));
+        let request = HcaptchaRequest::new_from_response(&other_secret, &token).unwrap();
+        assert!(client.verify_client_response(request).await.is_err());
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+        #[cfg(feature = "trace")]
+        assert!(logs_contain("The response is cached"));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[tokio::test]
+    async fn cached_response_is_checked_against_policy() {
+        let token = random_string(100);
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let solved = chrono::Utc::now() - chrono::TimeDelta::milliseconds(1500);
+
+        let transport = crate::InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": true, "challenge_ts": solved.to_rfc3339() }).to_string(),
+        ));
+        let client = HcaptchaClient::builder()
+            .transport(transport.clone())
+            .policy(VerificationPolicy::new().max_age(Duration::from_secs(2)))
+            .verification_cache(VerificationCache::new(Duration::from_secs(60)))
+            .build()
+            .unwrap();
+
+        let request = || HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+        assert_ok!(client.verify_client_response(request()).await);
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        match client.verify_client_response(request()).await {
+            Err(HcaptchaError::Codes(codes)) => assert!(codes.contains(&Code::ChallengeTooOld)),
+            r => panic!("expected error codes, got {:?}", r),
+        }
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(feature = "trace", traced_test)]
+    async fn rate_limit_rejects_excess_verifications() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let transport = crate::InMemoryTransport::new();
+        for _ in 0..2 {
+            transport.push_response(TransportResponse::new(
+                200,
+                json!({ "success": true }).to_string(),
+            ));
+        }
+        let client = HcaptchaClient::builder()
+            .transport(transport.clone())
+            .replay_guard(InMemoryReplayGuard::new(Duration::from_secs(60)))
+            .rate_limit(RateLimit::new().rate(1, Duration::from_millis(50)))
+            .build()
+            .unwrap();
+
+        let request = HcaptchaRequest::new_from_response(&secret, &random_string(100)).unwrap();
+        assert_ok!(client.clone().verify_client_response(request).await);
+        let token = random_string(100);
+        let request = || HcaptchaRequest::new_from_response(&secret, &token).unwrap();
+        assert!(matches!(
+            client.verify_client_response(request()).await,
+            Err(HcaptchaError::RateLimited)
+        ));
+        assert_eq!(transport.requests().len(), 1);
+        #[cfg(feature = "trace")]
+        assert!(logs_contain("exceeds the rate limit"));
+
+        // The rejected token was not recorded by the replay guard.
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_ok!(client.verify_client_response(request()).await);
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(feature = "trace", traced_test)]
+    async fn circuit_breaker_fails_open_after_failures() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let transport = crate::InMemoryTransport::new();
+        for _ in 0..2 {
+            transport.push_response(TransportResponse::new(502, "Bad Gateway"));
+        }
+        let client = HcaptchaClient::builder()
+            .transport(transport.clone())
+            .circuit_breaker(
+                CircuitBreaker::new()
+                    .failure_threshold(2)
+                    .open_policy(OpenPolicy::FailOpen),
+            )
+            .build()
+            .unwrap();
+
+        for _ in 0..2 {
+            let request = HcaptchaRequest::new_from_response(&secret, &random_string(100)).unwrap();
+            assert!(matches!(
+                client.verify_client_response(request).await,
+                Err(HcaptchaError::HttpStatus { status: 502, .. })
+            ));
+        }
+        let request = HcaptchaRequest::new_from_response(&secret, &random_string(100)).unwrap();
+        let response = client.verify_client_response(request).await.unwrap();
+        assert!(response.success());
+        assert!(response.is_fail_open());
+        assert_eq!(transport.requests().len(), 2);
+        #[cfg(feature = "trace")]
+        assert!(logs_contain("Circuit breaker open"));
+    }
+
+    #[tokio::test]
+    async fn rejected_token_does_not_take_trial_call() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let transport = crate::InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(502, "Bad Gateway"));
+        for _ in 0..2 {
+            transport.push_response(TransportResponse::new(
+                200,
+                json!({ "success": true }).to_string(),
+            ));
+        }
+        let client = HcaptchaClient::builder()
+            .transport(transport.clone())
+            .replay_guard(InMemoryReplayGuard::new(Duration::from_secs(60)))
+            .circuit_breaker(
+                CircuitBreaker::new()
+                    .failure_threshold(1)
+                    .open_duration(Duration::from_millis(50)),
+            )
+            .build()
+            .unwrap();
+        let request = |token: &str| HcaptchaRequest::new_from_response(&secret, token).unwrap();
+
+        let seen = random_string(100);
+        assert!(matches!(
+            client.verify_client_response(request(&seen)).await,
+            Err(HcaptchaError::HttpStatus { status: 502, .. })
+        ));
+        // The token is not recorded while the circuit is open.
+        let waiting = random_string(100);
+        assert!(matches!(
+            client.verify_client_response(request(&waiting)).await,
+            Err(HcaptchaError::CircuitOpen)
+        ));
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(matches!(
+            client.verify_client_response(request(&seen)).await,
+            Err(HcaptchaError::Codes(_))
+        ));
+        assert_ok!(
+            client
+                .verify_client_response(request(&random_string(100)))
+                .await
+        );
+        assert_ok!(client.verify_client_response(request(&waiting)).await);
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    #[cfg_attr(feature = "trace", traced_test)]
+    async fn secondary_secret_is_tried_after_invalid_secret() {
+        let token = random_string(100);
+        let primary = format!("0x{}", hex::encode(random_string(20)));
+        let secondary = format!("0x{}", hex::encode(random_string(20)));
+
+        let transport = crate::InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": false, "error-codes": ["invalid-input-secret"] }).to_string(),
+        ));
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": true }).to_string(),
+        ));
+        let client = HcaptchaClient::builder()
+            .transport(transport.clone())
+            .secret_provider(
+                Secrets::new(&primary)
+                    .unwrap()
+                    .secondary(&secondary)
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        assert_ok!(
+            client
+                .verify_captcha(HcaptchaCaptcha::new(&token).unwrap())
+                .await
+        );
+        let requests = transport.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[0].1,
+            format!("response={}&secret={}", &token, &primary)
+        );
+        assert_eq!(
+            requests[1].1,
+            format!("response={}&secret={}", &token, &secondary)
+        );
+        #[cfg(feature = "trace")]
+        assert!(logs_contain("retrying with the secondary secret"));
+    }
+
+    #[tokio::test]
+    async fn secondary_secret_is_tried_with_replay_guard() {
+        let token = random_string(100);
+        let primary = format!("0x{}", hex::encode(random_string(20)));
+        let secondary = format!("0x{}", hex::encode(random_string(20)));
+
+        let transport = crate::InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": false, "error-codes": ["invalid-input-secret"] }).to_string(),
+        ));
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": true }).to_string(),
+        ));
+        let client = HcaptchaClient::builder()
+            .transport(transport.clone())
+            .replay_guard(InMemoryReplayGuard::new(Duration::from_secs(60)))
+            .secret_provider(
+                Secrets::new(&primary)
+                    .unwrap()
+                    .secondary(&secondary)
+                    .unwrap(),
+            )
+            .build()
+            .unwrap();
+
+        let captcha = || HcaptchaCaptcha::new(&token).unwrap();
+        assert_ok!(client.verify_captcha(captcha()).await);
+        assert_eq!(transport.requests().len(), 2);
+        match client.verify_captcha(captcha()).await {
+            Err(HcaptchaError::Codes(codes)) => {
+                assert!(codes.contains(&Code::InvalidAlreadySeen))
+            }
+            r => panic!("expected error codes, got {:?}", r),
+        }
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn invalid_secret_without_secondary_is_returned() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let transport = crate::InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": false, "error-codes": ["invalid-input-secret"] }).to_string(),
+        ));
+        let client = HcaptchaClient::builder()
+            .transport(transport.clone())
+            .secret_provider(Secrets::new(&secret).unwrap())
+            .build()
+            .unwrap();
+
+        match client
+            .verify_captcha(HcaptchaCaptcha::new(&random_string(100)).unwrap())
+            .await
+        {
+            Err(HcaptchaError::Codes(codes)) => assert!(codes.contains(&Code::InvalidSecret)),
+            r => panic!("expected error codes, got {:?}", r),
+        }
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    /// Transport answering after a delay and tracking the peak concurrency.
+    #[derive(Debug, Default)]
+    struct SlowTransport {
+        in_flight: std::sync::atomic::AtomicUsize,
+        peak: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl HcaptchaTransport for SlowTransport {
+        async fn post_form(
+            &self,
+            _url: &Url,
+            form: &str,
+        ) -> Result<TransportResponse, HcaptchaError> {
+            use std::sync::atomic::Ordering;
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(in_flight, Ordering::SeqCst);
+            // Answer later requests sooner to check the order of the results.
+            let delay = if form.contains("response=first") {
+                30
+            } else {
+                5
+            };
+            tokio::time::sleep(Duration::from_millis(delay)).await;
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            let body = if form.contains("response=bad") {
+                json!({ "success": false, "error-codes": ["invalid-input-response"] })
+            } else {
+                json!({ "success": true, "hostname": form.split('&').next().unwrap() })
+            };
+            Ok(TransportResponse::new(200, body.to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn many_requests_are_verified_in_order() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+        let transport = Arc::new(SlowTransport::default());
+        let client = HcaptchaClient {
+            transport: transport.clone(),
+            ..HcaptchaClient::with_transport(crate::InMemoryTransport::new())
+        };
+
+        let tokens = ["first", "second", "bad", "fourth", "fifth"];
+        let requests = tokens
+            .iter()
+            .map(|token| HcaptchaRequest::new_from_response(&secret, token).unwrap());
+        let results = client.verify_many(requests, 2).await;
+
+        assert_eq!(results.len(), tokens.len());
+        for (token, result) in tokens.iter().zip(results) {
+            match result {
+                Ok(response) => {
+                    assert_eq!(response.hostname(), Some(format!("response={}", token)))
+                }
+                Err(HcaptchaError::Codes(codes)) => {
+                    assert_eq!(*token, "bad");
+                    assert!(codes.contains(&Code::InvalidResponse));
+                }
+                Err(e) => panic!("unexpected error {:?}", e),
+            }
+        }
+        assert_eq!(transport.peak.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[cfg(feature = "enterprise")]
+    #[tokio::test]
+    async fn verdict_is_returned_with_response() {
+        let secret = format!("0x{}", hex::encode(random_string(20)));
+
+        let transport = crate::InMemoryTransport::new();
+        for _ in 0..2 {
+            transport.push_response(TransportResponse::new(
+                200,
+                json!({ "success": true, "score": 0.45 }).to_string(),
+            ));
+        }
+        let client = HcaptchaClient::builder()
+            .transport(transport)
+            .score_policy(ScorePolicy::new().action_thresholds("login", 0.2, 0.4))
+            .build()
+            .unwrap();
+
+        let request = HcaptchaRequest::new_from_response(&secret, &random_string(100)).unwrap();
+        let (response, verdict) = client.verify_with_verdict(request, None).await.unwrap();
+        assert_eq!(response.score(), Some(0.45));
+        assert_eq!(verdict, Verdict::Allow);
+
+        let request = HcaptchaRequest::new_from_response(&secret, &random_string(100)).unwrap();
+        let (_, verdict) = client
+            .verify_with_verdict(request, Some("login"))
+            .await
+            .unwrap();
+        assert_eq!(verdict, Verdict::Deny);
+    }
+
+    #[test]
+    fn body_excerpt_is_truncated() {
+        let body = "x".repeat(BODY_EXCERPT_LEN * 2);
+        assert_eq!(body_excerpt(body.as_bytes()).len(), BODY_EXCERPT_LEN);
+    }
+
     #[test]
     fn test_success_response() {
         let api_response = json!({
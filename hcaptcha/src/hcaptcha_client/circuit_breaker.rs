@@ -0,0 +1,299 @@
+//! Circuit breaker to stop calling the siteverify API while it is unreachable.
+//!
+//! # Example
+//! Stop calling the API for thirty seconds after five consecutive failures
+//! and accept verifications in the meantime.
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::{CircuitBreaker, HcaptchaClient, HcaptchaRequest, OpenPolicy};
+//!     use std::time::Duration;
+//!
+//!     let breaker = CircuitBreaker::new()
+//!         .failure_threshold(5)
+//!         .open_duration(Duration::from_secs(30))
+//!         .open_policy(OpenPolicy::FailOpen);
+//!
+//!     let client = HcaptchaClient::builder()
+//!         .circuit_breaker(breaker)
+//!         .build()?;
+//!
+//! #   let secret = "0x123456789abcde0f123456789abcdef012345678";
+//! #   let token = "10000000-aaaa-bbbb-cccc-000000000001";
+//!     let request = HcaptchaRequest::new_from_response(secret, token)?;
+//!     let response = client.verify_client_response(request).await?;
+//!     if response.is_fail_open() {
+//!         // the token was not verified, apply further checks
+//!     }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{HcaptchaCaptcha, HcaptchaError, HcaptchaResponse};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Function deciding a verification while the circuit is open.
+type Fallback = dyn Fn(&HcaptchaCaptcha) -> Result<HcaptchaResponse, HcaptchaError> + Send + Sync;
+
+/// What happens to a verification while the circuit is open.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Default, Clone)]
+pub enum OpenPolicy {
+    /// Reject the verification with [HcaptchaError::CircuitOpen].
+    #[default]
+    FailClosed,
+    /// Accept the verification with a successful response flagged by
+    /// [is_fail_open](HcaptchaResponse::is_fail_open).
+    ///
+    /// The token is not verified, so only use this policy where letting a
+    /// bot through is preferable to turning a user away.
+    FailOpen,
+    /// Decide the verification with the function.
+    Fallback(Arc<Fallback>),
+}
+
+impl fmt::Debug for OpenPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OpenPolicy::FailClosed => f.write_str("FailClosed"),
+            OpenPolicy::FailOpen => f.write_str("FailOpen"),
+            OpenPolicy::Fallback(_) => f.write_str("Fallback"),
+        }
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl OpenPolicy {
+    /// Decide the verification with the function.
+    ///
+    /// The function is called with the captcha submitted for verification
+    /// and returns the response or error reported for it.
+    pub fn fallback<F>(fallback: F) -> OpenPolicy
+    where
+        F: Fn(&HcaptchaCaptcha) -> Result<HcaptchaResponse, HcaptchaError> + Send + Sync + 'static,
+    {
+        OpenPolicy::Fallback(Arc::new(fallback))
+    }
+}
+
+/// Settings of the circuit breaker on the calls to the API.
+///
+/// The circuit opens after a number of consecutive calls fail to return a
+/// response, for example because the connection fails, times out or the API
+/// returns a server error. A response from the API, including one that
+/// rejects the token, counts as a success.
+///
+/// While the circuit is open the API is not called and verifications are
+/// decided by the [OpenPolicy]. After the open duration a single trial call
+/// is allowed: the circuit closes if it succeeds and opens again if it
+/// fails.
+///
+/// With the `trace` feature each change of state is logged as an event.
+/// Clones of the client share the circuit.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    /// Consecutive failures that open the circuit.
+    failure_threshold: u32,
+    /// Time the circuit stays open before a trial call.
+    open_duration: Duration,
+    /// What happens to a verification while the circuit is open.
+    open_policy: OpenPolicy,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl Default for CircuitBreaker {
+    fn default() -> CircuitBreaker {
+        CircuitBreaker {
+            failure_threshold: 5,
+            open_duration: Duration::from_secs(30),
+            open_policy: OpenPolicy::default(),
+        }
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl CircuitBreaker {
+    /// Create a circuit breaker that opens for thirty seconds after five
+    /// consecutive failures and fails closed.
+    pub fn new() -> CircuitBreaker {
+        CircuitBreaker::default()
+    }
+
+    /// Set the number of consecutive failures that open the circuit.
+    pub fn failure_threshold(mut self, failures: u32) -> Self {
+        self.failure_threshold = failures.max(1);
+        self
+    }
+
+    /// Set the time the circuit stays open before a trial call.
+    pub fn open_duration(mut self, duration: Duration) -> Self {
+        self.open_duration = duration;
+        self
+    }
+
+    /// Set what happens to a verification while the circuit is open.
+    pub fn open_policy(mut self, policy: OpenPolicy) -> Self {
+        self.open_policy = policy;
+        self
+    }
+}
+
+/// State of the circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Calls are made, counting the consecutive failures.
+    Closed(u32),
+    /// Calls are not made until the instant.
+    Open(Instant),
+    /// A trial call started at the instant is in progress.
+    HalfOpen(Instant),
+}
+
+/// Circuit shared by the clones of a client.
+#[derive(Debug)]
+pub(crate) struct Breaker {
+    /// Settings of the circuit breaker.
+    settings: CircuitBreaker,
+    /// State of the circuit.
+    state: Mutex<State>,
+}
+
+impl Breaker {
+    /// Create a closed circuit.
+    pub(crate) fn new(settings: CircuitBreaker) -> Breaker {
+        Breaker {
+            settings,
+            state: Mutex::new(State::Closed(0)),
+        }
+    }
+
+    /// Report if a call may be made.
+    pub(crate) fn allow(&self) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        match *state {
+            State::Closed(_) => true,
+            State::Open(until) if now >= until => {
+                #[cfg(feature = "trace")]
+                tracing::info!("Circuit breaker half open, trying a call to the API");
+                *state = State::HalfOpen(now);
+                true
+            }
+            // A trial call that never completed, for example because it was
+            // cancelled, does not hold the circuit half open forever.
+            State::HalfOpen(started) if now >= started + self.settings.open_duration => {
+                *state = State::HalfOpen(now);
+                true
+            }
+            State::Open(_) | State::HalfOpen(_) => false,
+        }
+    }
+
+    /// Record the outcome of a call.
+    pub(crate) fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        *state = match (*state, success) {
+            (State::Closed(_), true) => State::Closed(0),
+            (_, true) => {
+                #[cfg(feature = "trace")]
+                tracing::info!("Circuit breaker closed, the API is reachable");
+                State::Closed(0)
+            }
+            (State::Closed(failures), false) if failures + 1 < self.settings.failure_threshold => {
+                State::Closed(failures + 1)
+            }
+            (State::Open(until), false) => State::Open(until),
+            (_previous, false) => {
+                #[cfg(feature = "trace")]
+                tracing::warn!(
+                    "Circuit breaker open for {:?} after a failed call in state {:?}",
+                    self.settings.open_duration,
+                    _previous
+                );
+                State::Open(Instant::now() + self.settings.open_duration)
+            }
+        };
+    }
+
+    /// Decide the verification of the captcha while the circuit is open.
+    pub(crate) fn open(
+        &self,
+        captcha: &HcaptchaCaptcha,
+    ) -> Result<HcaptchaResponse, HcaptchaError> {
+        #[cfg(feature = "trace")]
+        tracing::debug!(
+            "Circuit breaker open, applying {:?}",
+            self.settings.open_policy
+        );
+        match &self.settings.open_policy {
+            OpenPolicy::FailClosed => Err(HcaptchaError::CircuitOpen),
+            OpenPolicy::FailOpen => Ok(HcaptchaResponse::fail_open()),
+            OpenPolicy::Fallback(fallback) => fallback(captcha),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn captcha() -> HcaptchaCaptcha {
+        HcaptchaCaptcha::new("token").unwrap()
+    }
+
+    #[test]
+    fn circuit_opens_after_consecutive_failures() {
+        let breaker = Breaker::new(CircuitBreaker::new().failure_threshold(2));
+        breaker.record(false);
+        breaker.record(true);
+        breaker.record(false);
+        assert!(breaker.allow());
+        breaker.record(false);
+        assert!(!breaker.allow());
+    }
+
+    #[test]
+    fn trial_call_closes_or_reopens_circuit() {
+        let breaker = Breaker::new(
+            CircuitBreaker::new()
+                .failure_threshold(1)
+                .open_duration(Duration::ZERO),
+        );
+        breaker.record(false);
+        assert!(breaker.allow());
+        assert!(matches!(*breaker.state.lock().unwrap(), State::HalfOpen(_)));
+        breaker.record(false);
+        assert!(matches!(*breaker.state.lock().unwrap(), State::Open(_)));
+        assert!(breaker.allow());
+        breaker.record(true);
+        assert_eq!(*breaker.state.lock().unwrap(), State::Closed(0));
+    }
+
+    #[test]
+    fn open_policy_decides_verification() {
+        let breaker = Breaker::new(CircuitBreaker::new());
+        assert!(matches!(
+            breaker.open(&captcha()),
+            Err(HcaptchaError::CircuitOpen)
+        ));
+
+        let breaker = Breaker::new(CircuitBreaker::new().open_policy(OpenPolicy::FailOpen));
+        let response = breaker.open(&captcha()).unwrap();
+        assert!(response.success());
+        assert!(response.is_fail_open());
+
+        let breaker = Breaker::new(CircuitBreaker::new().open_policy(OpenPolicy::fallback(
+            |captcha| {
+                assert_eq!(captcha.clone().response().to_string(), "token");
+                Err(HcaptchaError::RateLimited)
+            },
+        )));
+        assert!(matches!(
+            breaker.open(&captcha()),
+            Err(HcaptchaError::RateLimited)
+        ));
+    }
+}
@@ -0,0 +1,403 @@
+//! Builder for a [HcaptchaClient] with custom connection settings.
+//!
+//! # Example
+//! Build a client that gives up on a verification after five seconds.
+//! ```
+//! # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::HcaptchaClient;
+//!     use std::time::Duration;
+//!
+//!     let client = HcaptchaClient::builder()
+//!         .connect_timeout(Duration::from_secs(1))
+//!         .timeout(Duration::from_secs(2))
+//!         .deadline(Duration::from_secs(5))
+//!         .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(feature = "enterprise")]
+use super::ScorePolicy;
+use super::{
+    Breaker, CircuitBreaker, HcaptchaClient, Limiter, RateLimit, RemoteipPrivacy, ReplayGuard,
+    RetryPolicy, SecretProvider, SecretRegistry, VerificationCache, VerificationPolicy, VERIFY_URL,
+};
+#[cfg(feature = "reqwest")]
+use crate::ReqwestTransport;
+use crate::{HcaptchaError, HcaptchaTransport};
+#[cfg(feature = "reqwest")]
+use reqwest::{Client, ClientBuilder, Proxy};
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// Build a [HcaptchaClient] with timeouts, a verification deadline and
+/// connection settings.
+///
+/// By default no timeouts are applied, matching [HcaptchaClient::new].
+///
+/// The connection settings configure the reqwest client and are available
+/// with the `reqwest` feature. They are ignored if a
+/// [transport](HcaptchaClientBuilder::transport) is provided.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Default, Clone)]
+pub struct HcaptchaClientBuilder {
+    /// Url for the endpoint, defaults to [VERIFY_URL].
+    url: Option<String>,
+    /// Transport used in place of a reqwest client.
+    transport: Option<Arc<dyn HcaptchaTransport>>,
+    /// Timeout for each request, from sending to reading the body.
+    #[cfg(feature = "reqwest")]
+    timeout: Option<Duration>,
+    /// Timeout for establishing a connection.
+    #[cfg(feature = "reqwest")]
+    connect_timeout: Option<Duration>,
+    /// Overall time allowed for a verification to complete.
+    deadline: Option<Duration>,
+    /// User agent sent with each request.
+    #[cfg(feature = "reqwest")]
+    user_agent: Option<String>,
+    /// Proxy url used for all requests.
+    #[cfg(feature = "reqwest")]
+    proxy: Option<String>,
+    /// Time an idle connection is kept in the pool.
+    #[cfg(feature = "reqwest")]
+    pool_idle_timeout: Option<Duration>,
+    /// Maximum number of idle connections kept per host.
+    #[cfg(feature = "reqwest")]
+    pool_max_idle_per_host: Option<usize>,
+    /// Policy to retry transient failures.
+    retry: Option<RetryPolicy>,
+    /// Policy checked against a successful response.
+    policy: Option<VerificationPolicy>,
+    /// Policy deciding the verdict from the score of the response.
+    #[cfg(feature = "enterprise")]
+    score_policy: Option<ScorePolicy>,
+    /// How much of the remoteip is sent to the API.
+    remoteip_privacy: RemoteipPrivacy,
+    /// Secrets of the sites verified by the client.
+    secrets: Option<SecretRegistry>,
+    /// Provider of the secret used if no registry is set.
+    secret_provider: Option<Arc<dyn SecretProvider>>,
+    /// Record of the tokens sent to the API.
+    replay_guard: Option<Arc<dyn ReplayGuard>>,
+    /// Cache of successful verifications.
+    cache: Option<VerificationCache>,
+    /// Limit on the calls to the API.
+    rate_limit: Option<RateLimit>,
+    /// Circuit breaker on the calls to the API.
+    circuit_breaker: Option<CircuitBreaker>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl HcaptchaClientBuilder {
+    /// Create a new builder with the default settings.
+    pub fn new() -> HcaptchaClientBuilder {
+        HcaptchaClientBuilder::default()
+    }
+
+    /// Set the url for the hcaptcha API.
+    pub fn url(mut self, url: &str) -> Self {
+        self.url = Some(url.to_owned());
+        self
+    }
+
+    /// Use the transport to send requests to the API.
+    ///
+    /// By default a reqwest client configured with the connection settings
+    /// of the builder is used.
+    pub fn transport<T>(mut self, transport: T) -> Self
+    where
+        T: HcaptchaTransport + 'static,
+    {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Set the timeout for each request to the API.
+    ///
+    /// The timeout applies from when the request starts connecting until the
+    /// response body has been read.
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the timeout for establishing a connection to the API.
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the overall time allowed for a verification to complete.
+    ///
+    /// If the deadline passes [HcaptchaError::Timeout] is returned.
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Set the user agent sent with each request.
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    /// Send all requests through the proxy at the url.
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+    pub fn proxy(mut self, proxy: &str) -> Self {
+        self.proxy = Some(proxy.to_owned());
+        self
+    }
+
+    /// Set how long an idle connection is kept in the pool.
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum number of idle connections kept per host.
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Retry transient failures according to the [RetryPolicy].
+    ///
+    /// Retries are disabled by default.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
+
+    /// Check each successful response against the [VerificationPolicy].
+    ///
+    /// By default no policy is applied.
+    pub fn policy(mut self, policy: VerificationPolicy) -> Self {
+        self.policy = Some(policy);
+        self
+    }
+
+    /// Decide the verdict of [verify_with_verdict](HcaptchaClient::verify_with_verdict)
+    /// with the [ScorePolicy].
+    #[cfg(feature = "enterprise")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+    pub fn score_policy(mut self, policy: ScorePolicy) -> Self {
+        self.score_policy = Some(policy);
+        self
+    }
+
+    /// Set how much of the remoteip is sent to the API with the
+    /// [RemoteipPrivacy].
+    ///
+    /// By default the full address is sent.
+    pub fn remoteip_privacy(mut self, privacy: RemoteipPrivacy) -> Self {
+        self.remoteip_privacy = privacy;
+        self
+    }
+
+    /// Use the secrets in the [SecretRegistry] to verify captchas with
+    /// [verify_captcha](HcaptchaClient::verify_captcha).
+    pub fn secret_registry(mut self, registry: SecretRegistry) -> Self {
+        self.secrets = Some(registry);
+        self
+    }
+
+    /// Fetch the secret to verify captchas with
+    /// [verify_captcha](HcaptchaClient::verify_captcha) from the
+    /// [SecretProvider].
+    ///
+    /// The provider is consulted on every verification. Wrap it in a
+    /// [CachedSecretProvider](super::CachedSecretProvider) to reuse the
+    /// secrets between verifications. A [SecretRegistry], if set, takes
+    /// precedence over the provider.
+    pub fn secret_provider<P>(mut self, provider: P) -> Self
+    where
+        P: SecretProvider + 'static,
+    {
+        self.secret_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Record each token with the [ReplayGuard] before it is sent to the
+    /// API and reject a token already recorded with
+    /// [Code::InvalidAlreadySeen](crate::Code::InvalidAlreadySeen).
+    ///
+    /// By default tokens are not recorded.
+    pub fn replay_guard<G>(mut self, guard: G) -> Self
+    where
+        G: ReplayGuard + 'static,
+    {
+        self.replay_guard = Some(Arc::new(guard));
+        self
+    }
+
+    /// Return the cached response when a token is verified again with the
+    /// same secret while the [VerificationCache] holds it.
+    ///
+    /// By default responses are not cached.
+    pub fn verification_cache(mut self, cache: VerificationCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Limit the rate and concurrency of the calls to the API with the
+    /// [RateLimit].
+    ///
+    /// By default the calls are not limited.
+    pub fn rate_limit(mut self, limit: RateLimit) -> Self {
+        self.rate_limit = Some(limit);
+        self
+    }
+
+    /// Stop calling the API while it is unreachable with the
+    /// [CircuitBreaker].
+    ///
+    /// By default the API is always called.
+    pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Build the [HcaptchaClient].
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError] is returned if the url or proxy url cannot be parsed
+    /// or the HTTP client cannot be initialised. Without the `reqwest`
+    /// feature an error is returned if no transport has been provided.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "Build HcaptchaClient.", level = "debug")
+    )]
+    pub fn build(self) -> Result<HcaptchaClient, HcaptchaError> {
+        let url = Url::parse(self.url.as_deref().unwrap_or(VERIFY_URL))?;
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => self.reqwest_transport()?,
+        };
+
+        Ok(HcaptchaClient {
+            transport,
+            url,
+            deadline: self.deadline,
+            retry: self.retry,
+            policy: self.policy,
+            #[cfg(feature = "enterprise")]
+            score_policy: self.score_policy,
+            remoteip_privacy: self.remoteip_privacy,
+            secrets: self.secrets,
+            secret_provider: self.secret_provider,
+            replay_guard: self.replay_guard,
+            cache: self.cache,
+            limiter: self
+                .rate_limit
+                .as_ref()
+                .map(|limit| Arc::new(Limiter::new(limit))),
+            breaker: self
+                .circuit_breaker
+                .map(|breaker| Arc::new(Breaker::new(breaker))),
+        })
+    }
+
+    #[cfg(feature = "reqwest")]
+    fn reqwest_transport(&self) -> Result<Arc<dyn HcaptchaTransport>, HcaptchaError> {
+        let mut builder: ClientBuilder = Client::builder();
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(Proxy::all(proxy)?);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+
+        Ok(Arc::new(ReqwestTransport::with_timeouts(
+            builder.build()?,
+            self.timeout,
+            self.connect_timeout,
+        )))
+    }
+
+    #[cfg(not(feature = "reqwest"))]
+    fn reqwest_transport(&self) -> Result<Arc<dyn HcaptchaTransport>, HcaptchaError> {
+        Err(HcaptchaError::Transport(
+            "no transport provided and the reqwest feature is not enabled".into(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::assert_err;
+    #[cfg(feature = "reqwest")]
+    use claims::assert_ok;
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn default_builder_uses_verify_url() {
+        let client = HcaptchaClientBuilder::new().build().unwrap();
+        assert_eq!(client.url.as_str(), VERIFY_URL);
+        assert!(client.deadline.is_none());
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn builder_sets_url_and_deadline() {
+        let client = HcaptchaClientBuilder::new()
+            .url("https://domain.com/siteverify")
+            .timeout(Duration::from_secs(2))
+            .deadline(Duration::from_secs(5))
+            .user_agent("hcaptcha-test")
+            .pool_max_idle_per_host(4)
+            .pool_idle_timeout(Duration::from_secs(30))
+            .build()
+            .unwrap();
+        assert_eq!(client.url.as_str(), "https://domain.com/siteverify");
+        assert_eq!(client.deadline, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn builder_uses_transport() {
+        let client = HcaptchaClientBuilder::new()
+            .transport(crate::InMemoryTransport::new())
+            .build()
+            .unwrap();
+        assert!(format!("{:?}", client.transport).contains("InMemoryTransport"));
+    }
+
+    #[test]
+    fn invalid_url_is_rejected() {
+        assert_err!(HcaptchaClientBuilder::new().url("not a url").build());
+    }
+
+    #[cfg(feature = "reqwest")]
+    #[test]
+    fn proxy_is_accepted() {
+        assert_ok!(HcaptchaClientBuilder::new()
+            .proxy("http://proxy.domain.com:8080")
+            .build());
+    }
+}
@@ -1,11 +1,37 @@
+use crate::domain::HcaptchaSecret;
 use crate::HcaptchaRequest;
+use std::fmt;
 
-#[derive(Debug, Clone, serde::Serialize)]
+/// Text shown in place of the token and secret.
+const REDACTED: &str = "[REDACTED]";
+
+#[derive(Clone, serde::Serialize)]
 pub(crate) struct HcaptchaForm {
     response: String,
     remoteip: Option<String>,
     sitekey: Option<String>,
-    secret: String,
+    secret: HcaptchaSecret,
+}
+
+impl fmt::Debug for HcaptchaForm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HcaptchaForm")
+            .field("response", &REDACTED)
+            .field("remoteip", &self.remoteip)
+            .field("sitekey", &self.sitekey)
+            .field("secret", &self.secret)
+            .finish()
+    }
+}
+
+/// The form with the token and secret masked, for logging.
+#[cfg(feature = "trace")]
+#[derive(serde::Serialize)]
+struct RedactedForm<'a> {
+    response: &'a str,
+    remoteip: Option<&'a str>,
+    sitekey: Option<&'a str>,
+    secret: &'a str,
 }
 
 impl From<HcaptchaRequest> for HcaptchaForm {
@@ -17,7 +43,83 @@ impl From<HcaptchaRequest> for HcaptchaForm {
             response: request.captcha().response.to_string(),
             remoteip,
             sitekey,
-            secret: request.secret().to_string(),
+            secret: request.secret(),
         }
     }
 }
+
+impl HcaptchaForm {
+    pub(crate) fn response(&self) -> &str {
+        &self.response
+    }
+
+    pub(crate) fn secret(&self) -> &HcaptchaSecret {
+        &self.secret
+    }
+
+    pub(crate) fn sitekey(&self) -> Option<&str> {
+        self.sitekey.as_deref()
+    }
+
+    pub(crate) fn set_sitekey(&mut self, sitekey: &str) {
+        self.sitekey = Some(sitekey.to_owned());
+    }
+
+    pub(crate) fn remoteip(&self) -> Option<&str> {
+        self.remoteip.as_deref()
+    }
+
+    pub(crate) fn set_remoteip(&mut self, remoteip: Option<String>) {
+        self.remoteip = remoteip;
+    }
+
+    /// Encode the form with the token and secret masked.
+    #[cfg(feature = "trace")]
+    pub(crate) fn redacted(&self) -> String {
+        serde_urlencoded::to_string(RedactedForm {
+            response: REDACTED,
+            remoteip: self.remoteip(),
+            sitekey: self.sitekey(),
+            secret: REDACTED,
+        })
+        .unwrap_or_else(|_| "form corrupted".to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "0x123456789abcde0f123456789abcdef012345678";
+    const TOKEN: &str = "10000000-aaaa-bbbb-cccc-000000000001";
+
+    fn form() -> HcaptchaForm {
+        HcaptchaRequest::new_from_response(SECRET, TOKEN)
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn form_is_encoded_with_secret() {
+        assert_eq!(
+            serde_urlencoded::to_string(form()).unwrap(),
+            format!("response={}&secret={}", TOKEN, SECRET)
+        );
+    }
+
+    #[test]
+    fn debug_output_masks_token_and_secret() {
+        let debug = format!("{:?}", form());
+        assert!(!debug.contains(SECRET));
+        assert!(!debug.contains(TOKEN));
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn redacted_output_masks_token_and_secret() {
+        assert_eq!(
+            form().redacted(),
+            "response=%5BREDACTED%5D&secret=%5BREDACTED%5D"
+        );
+    }
+}
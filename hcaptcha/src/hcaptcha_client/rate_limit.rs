@@ -0,0 +1,252 @@
+//! Client side limit on the calls to the siteverify API.
+//!
+//! # Example
+//! Allow 50 verifications a second with bursts of 100, at most 20 at a
+//! time, and wait up to half a second for capacity before giving up.
+//! ```
+//! # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::{HcaptchaClient, Overflow, RateLimit};
+//!     use std::time::Duration;
+//!
+//!     let limit = RateLimit::new()
+//!         .rate(50, Duration::from_secs(1))
+//!         .burst(100)
+//!         .max_in_flight(20)
+//!         .overflow(Overflow::Wait(Duration::from_millis(500)));
+//!
+//!     let client = HcaptchaClient::builder()
+//!         .rate_limit(limit)
+//!         .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::HcaptchaError;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// What happens to a verification that exceeds the [RateLimit].
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Reject the verification with [HcaptchaError::RateLimited].
+    #[default]
+    FailFast,
+    /// Wait up to the duration for capacity, then reject the verification
+    /// with [HcaptchaError::RateLimited].
+    Wait(Duration),
+}
+
+/// Limit on the rate and the number of concurrent calls to the API.
+///
+/// The rate is enforced with a token bucket holding up to the burst size,
+/// refilled at the rate. The calls in flight are those sent to the API and
+/// awaiting a response, including retries. A verification answered from a
+/// [VerificationCache](crate::VerificationCache) does not count against the
+/// limit.
+///
+/// Clones of the client share the limit. By default no limit is applied.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Default, Clone)]
+pub struct RateLimit {
+    /// Number of calls allowed in each period.
+    rate: Option<(u32, Duration)>,
+    /// Number of calls allowed at once after an idle period.
+    burst: Option<u32>,
+    /// Maximum number of calls in flight.
+    max_in_flight: Option<usize>,
+    /// What happens to a call that exceeds the limit.
+    overflow: Overflow,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl RateLimit {
+    /// Create a limit that allows any rate and concurrency.
+    pub fn new() -> RateLimit {
+        RateLimit::default()
+    }
+
+    /// Allow the number of calls in each period.
+    pub fn rate(mut self, calls: u32, period: Duration) -> Self {
+        self.rate = Some((calls.max(1), period));
+        self
+    }
+
+    /// Set the number of calls allowed at once after an idle period.
+    ///
+    /// Defaults to the number of calls in a period of the rate.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = Some(burst.max(1));
+        self
+    }
+
+    /// Set the maximum number of calls in flight.
+    pub fn max_in_flight(mut self, max: usize) -> Self {
+        self.max_in_flight = Some(max.max(1));
+        self
+    }
+
+    /// Set what happens to a call that exceeds the limit.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+}
+
+/// State of a [RateLimit] shared by the clones of a client.
+#[derive(Debug)]
+pub(crate) struct Limiter {
+    /// Token bucket enforcing the rate.
+    bucket: Option<Mutex<Bucket>>,
+    /// Permits for the calls in flight.
+    in_flight: Option<Arc<Semaphore>>,
+    /// What happens to a call that exceeds the limit.
+    overflow: Overflow,
+}
+
+/// Token bucket refilled at a constant rate.
+#[derive(Debug)]
+struct Bucket {
+    /// Tokens added each second.
+    per_second: f64,
+    /// Maximum number of tokens.
+    capacity: f64,
+    /// Tokens available.
+    tokens: f64,
+    /// Time the tokens were last refilled.
+    refilled: Instant,
+}
+
+impl Bucket {
+    /// Take a token, or return the time until a token is available.
+    fn take(&mut self) -> Result<(), Duration> {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.refilled = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(
+                (1.0 - self.tokens) / self.per_second,
+            ))
+        }
+    }
+}
+
+impl Limiter {
+    /// Create the shared state of the limit.
+    pub(crate) fn new(limit: &RateLimit) -> Limiter {
+        let bucket = limit.rate.map(|(calls, period)| {
+            let capacity = f64::from(limit.burst.unwrap_or(calls));
+            Mutex::new(Bucket {
+                per_second: f64::from(calls) / period.as_secs_f64().max(f64::EPSILON),
+                capacity,
+                tokens: capacity,
+                refilled: Instant::now(),
+            })
+        });
+        Limiter {
+            bucket,
+            in_flight: limit.max_in_flight.map(|max| Arc::new(Semaphore::new(max))),
+            overflow: limit.overflow,
+        }
+    }
+
+    /// Wait for capacity to make a call.
+    ///
+    /// The returned permit counts the call as in flight until it is
+    /// dropped.
+    pub(crate) async fn acquire(&self) -> Result<Option<OwnedSemaphorePermit>, HcaptchaError> {
+        let started = Instant::now();
+        let wait = match self.overflow {
+            Overflow::FailFast => Duration::ZERO,
+            Overflow::Wait(wait) => wait,
+        };
+
+        let permit = match &self.in_flight {
+            Some(semaphore) => Some(match semaphore.clone().try_acquire_owned() {
+                Ok(permit) => permit,
+                Err(_) if wait.is_zero() => return Err(rate_limited("in flight")),
+                Err(_) => tokio::time::timeout(wait, semaphore.clone().acquire_owned())
+                    .await
+                    .ok()
+                    .and_then(Result::ok)
+                    .ok_or_else(|| rate_limited("in flight"))?,
+            }),
+            None => None,
+        };
+
+        if let Some(bucket) = &self.bucket {
+            loop {
+                let taken = bucket.lock().unwrap_or_else(|e| e.into_inner()).take();
+                match taken {
+                    Ok(()) => break,
+                    Err(delay) if started.elapsed() + delay <= wait => {
+                        tokio::time::sleep(delay).await
+                    }
+                    Err(_) => return Err(rate_limited("rate")),
+                }
+            }
+        }
+        Ok(permit)
+    }
+}
+
+/// Error for a call that exceeds the limit.
+fn rate_limited(_limit: &str) -> HcaptchaError {
+    #[cfg(feature = "trace")]
+    tracing::debug!("The verification exceeds the {} limit", _limit);
+    HcaptchaError::RateLimited
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rate_is_limited_to_burst() {
+        let limiter = Limiter::new(&RateLimit::new().rate(1, Duration::from_secs(3600)).burst(2));
+        assert!(limiter.acquire().await.is_ok());
+        assert!(limiter.acquire().await.is_ok());
+        assert!(matches!(
+            limiter.acquire().await,
+            Err(HcaptchaError::RateLimited)
+        ));
+    }
+
+    #[tokio::test]
+    async fn overflow_waits_for_token() {
+        let limiter = Limiter::new(
+            &RateLimit::new()
+                .rate(1, Duration::from_millis(20))
+                .overflow(Overflow::Wait(Duration::from_secs(1))),
+        );
+        assert!(limiter.acquire().await.is_ok());
+        let started = Instant::now();
+        assert!(limiter.acquire().await.is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(10));
+    }
+
+    #[tokio::test]
+    async fn calls_in_flight_are_limited() {
+        let limiter = Limiter::new(&RateLimit::new().max_in_flight(1));
+        let permit = limiter.acquire().await.unwrap();
+        assert!(limiter.acquire().await.is_err());
+        drop(permit);
+        assert!(limiter.acquire().await.is_ok());
+
+        let limiter = Limiter::new(
+            &RateLimit::new()
+                .max_in_flight(1)
+                .overflow(Overflow::Wait(Duration::from_millis(10))),
+        );
+        let _permit = limiter.acquire().await.unwrap();
+        assert!(matches!(
+            limiter.acquire().await,
+            Err(HcaptchaError::RateLimited)
+        ));
+    }
+}
@@ -0,0 +1,83 @@
+//! Privacy setting for the remoteip sent to the siteverify API.
+//!
+//! # Example
+//! Send only the network of the user, not the full address.
+//! ```
+//! # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::{HcaptchaClient, RemoteipPrivacy};
+//!
+//!     let client = HcaptchaClient::builder()
+//!         .remoteip_privacy(RemoteipPrivacy::Truncate)
+//!         .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::hcaptcha_form::HcaptchaForm;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// How much of the remoteip of a request is sent to the API.
+///
+/// The setting applies to every request verified by the client, whether the
+/// remoteip was set on the [HcaptchaCaptcha](crate::HcaptchaCaptcha) or
+/// supplied by a framework integration.
+///
+/// By default the full address is sent.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteipPrivacy {
+    /// Send the full address.
+    #[default]
+    Full,
+    /// Send the network of the address: the /24 network of an IPv4 address
+    /// or the /48 network of an IPv6 address.
+    Truncate,
+    /// Do not send the remoteip.
+    Withhold,
+}
+
+impl RemoteipPrivacy {
+    /// Apply the setting to the remoteip of the form.
+    pub(crate) fn apply(self, form: &mut HcaptchaForm) {
+        let remoteip = match self {
+            RemoteipPrivacy::Full => return,
+            RemoteipPrivacy::Truncate => form
+                .remoteip()
+                .and_then(|remoteip| remoteip.parse::<IpAddr>().ok())
+                .map(|ip| truncate(ip).to_string()),
+            RemoteipPrivacy::Withhold => None,
+        };
+        form.set_remoteip(remoteip);
+    }
+}
+
+/// Clear the host bits of the address.
+fn truncate(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, c, _] = v4.octets();
+            IpAddr::V4(Ipv4Addr::new(a, b, c, 0))
+        }
+        IpAddr::V6(v6) => {
+            let [a, b, c, ..] = v6.segments();
+            IpAddr::V6(Ipv6Addr::new(a, b, c, 0, 0, 0, 0, 0))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addresses_are_truncated() {
+        assert_eq!(
+            truncate("192.0.2.123".parse().unwrap()).to_string(),
+            "192.0.2.0"
+        );
+        assert_eq!(
+            truncate("2001:db8:cafe:1:2:3:4:5".parse().unwrap()).to_string(),
+            "2001:db8:cafe::"
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Local record of verified tokens to reject a token submitted twice.
+//!
+//! The API reports a token that has already been verified with
+//! [Code::InvalidAlreadySeen](crate::Code::InvalidAlreadySeen), but only
+//! after a round trip, and a token submitted concurrently to two instances
+//! of an application can be verified by both. A [ReplayGuard] records the
+//! hash of each token before it is sent to the API and rejects a token
+//! already recorded without calling the API.
+//!
+//! [InMemoryReplayGuard] records the tokens seen by one process. Implement
+//! [ReplayGuard] over a shared store, such as Redis with `SET NX EX`, to
+//! reject tokens seen by any instance.
+//!
+//! # Example
+//! ```
+//! # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::{HcaptchaClient, InMemoryReplayGuard};
+//!     use std::time::Duration;
+//!
+//!     let client = HcaptchaClient::builder()
+//!         .replay_guard(InMemoryReplayGuard::new(Duration::from_secs(120)))
+//!         .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::HcaptchaError;
+use async_trait::async_trait;
+use sha2::{Digest, Sha256};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// SHA-256 hash of a token.
+///
+/// The hash identifies the token without storing it.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TokenHash([u8; 32]);
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl TokenHash {
+    /// Hash the token.
+    pub(crate) fn new(token: &str) -> TokenHash {
+        TokenHash(Sha256::digest(token.as_bytes()).into())
+    }
+
+    /// Hash the token together with the secret it is verified with.
+    pub(crate) fn with_secret(token: &str, secret: &str) -> TokenHash {
+        TokenHash(
+            Sha256::new()
+                .chain_update(token.as_bytes())
+                .chain_update([0])
+                .chain_update(secret.as_bytes())
+                .finalize()
+                .into(),
+        )
+    }
+
+    /// Get the bytes of the hash.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// The hash as lower case hex, suitable as a key in a shared store.
+impl fmt::Display for TokenHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+/// Record the tokens sent to the API to reject a token submitted twice.
+#[async_trait]
+pub trait ReplayGuard: fmt::Debug + Send + Sync {
+    /// Record the token and report if it was not already recorded.
+    ///
+    /// The check and the record must be a single atomic operation so that
+    /// concurrent submissions of a token are accepted only once. Errors
+    /// raised by a shared store can be reported with
+    /// [HcaptchaError::ReplayGuard].
+    async fn record(&self, token: &TokenHash) -> Result<bool, HcaptchaError>;
+
+    /// Forget a recorded token.
+    ///
+    /// Called when the API has not used the token, because the connection
+    /// to the API failed or the API rejected the secret, so the token can be
+    /// submitted again. By default the token is kept.
+    async fn forget(&self, _token: &TokenHash) -> Result<(), HcaptchaError> {
+        Ok(())
+    }
+}
+
+/// Record the tokens seen by this process for a time to live.
+///
+/// The time to live should cover the validity of a token, about two minutes.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug)]
+pub struct InMemoryReplayGuard {
+    /// Time a token is recorded.
+    ttl: Duration,
+    /// Recorded tokens.
+    seen: Mutex<Seen>,
+}
+
+/// Recorded tokens and the order they expire.
+#[derive(Debug, Default)]
+struct Seen {
+    /// Hashes of the recorded tokens.
+    tokens: HashSet<TokenHash>,
+    /// Time each token was recorded, oldest first.
+    expiry: VecDeque<(Instant, TokenHash)>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl InMemoryReplayGuard {
+    /// Record the tokens for the time to live.
+    pub fn new(ttl: Duration) -> InMemoryReplayGuard {
+        InMemoryReplayGuard {
+            ttl,
+            seen: Mutex::new(Seen::default()),
+        }
+    }
+
+    /// Get the number of tokens recorded.
+    pub fn len(&self) -> usize {
+        self.seen().tokens.len()
+    }
+
+    /// Report if no tokens are recorded.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn seen(&self) -> std::sync::MutexGuard<'_, Seen> {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        while let Some((recorded, token)) = seen.expiry.front().copied() {
+            if recorded.elapsed() < self.ttl {
+                break;
+            }
+            seen.expiry.pop_front();
+            seen.tokens.remove(&token);
+        }
+        seen
+    }
+}
+
+#[async_trait]
+impl ReplayGuard for InMemoryReplayGuard {
+    async fn record(&self, token: &TokenHash) -> Result<bool, HcaptchaError> {
+        let mut seen = self.seen();
+        if !seen.tokens.insert(*token) {
+            return Ok(false);
+        }
+        seen.expiry.push_back((Instant::now(), *token));
+        Ok(true)
+    }
+
+    async fn forget(&self, token: &TokenHash) -> Result<(), HcaptchaError> {
+        let mut seen = self.seen();
+        seen.tokens.remove(token);
+        seen.expiry.retain(|(_, recorded)| recorded != token);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_is_hex_sha256() {
+        assert_eq!(
+            TokenHash::new("abc").to_string(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[tokio::test]
+    async fn token_is_recorded_once() {
+        let guard = InMemoryReplayGuard::new(Duration::from_secs(60));
+        let token = TokenHash::new("token");
+        assert!(guard.record(&token).await.unwrap());
+        assert!(!guard.record(&token).await.unwrap());
+        assert!(guard.record(&TokenHash::new("other")).await.unwrap());
+        assert_eq!(guard.len(), 2);
+
+        guard.forget(&token).await.unwrap();
+        assert!(guard.record(&token).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn tokens_expire_after_ttl() {
+        let guard = InMemoryReplayGuard::new(Duration::ZERO);
+        let token = TokenHash::new("token");
+        assert!(guard.record(&token).await.unwrap());
+        assert!(guard.record(&token).await.unwrap());
+        assert!(guard.is_empty());
+    }
+}
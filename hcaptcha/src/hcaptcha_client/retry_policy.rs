@@ -0,0 +1,369 @@
+//! Retry policy for transient failures calling the siteverify API.
+//!
+//! # Example
+//! Retry up to four times on connection failures, 429 and 5xx responses.
+//! ```
+//! # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::{HcaptchaClient, RetryPolicy};
+//!     use std::time::Duration;
+//!
+//!     let policy = RetryPolicy::new()
+//!         .max_attempts(4)
+//!         .initial_backoff(Duration::from_millis(200))
+//!         .max_backoff(Duration::from_secs(2));
+//!
+//!     let client = HcaptchaClient::builder()
+//!         .retry(policy)
+//!         .deadline(Duration::from_secs(10))
+//!         .build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{Code, HcaptchaError, HcaptchaResponse};
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, SystemTime};
+
+/// Codes that are a definitive answer from the API and are never retried.
+const DEFINITIVE_CODES: [Code; 5] = [
+    Code::InvalidAlreadySeen,
+    Code::InvalidResponse,
+    Code::MissingResponse,
+    Code::InvalidSecret,
+    Code::SiteSecretMismatch,
+];
+
+/// Policy to retry a verification after a transient failure.
+///
+/// A verification is retried when:
+/// - the request fails to connect,
+/// - the request times out or fails after it was sent, or a custom
+///   [HcaptchaTransport](crate::HcaptchaTransport) returns an error, if
+///   [retry_timeouts](RetryPolicy::retry_timeouts) is enabled,
+/// - the API returns one of the retryable HTTP statuses (by default 429, 500,
+///   502, 503 and 504),
+/// - the API reports one of the retryable [Code]s (by default none).
+///
+/// Codes that are a definitive answer about the token or secret, such as
+/// [Code::InvalidAlreadySeen], are never retried.
+///
+/// The delay between attempts grows exponentially from the initial backoff up
+/// to the maximum backoff, with random jitter applied. A `Retry-After` header
+/// returned by the API is honoured in place of the backoff. If it asks for a
+/// delay longer than the maximum backoff the verification is not retried.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first.
+    max_attempts: u32,
+    /// Delay before the first retry.
+    initial_backoff: Duration,
+    /// Upper limit for the delay between attempts.
+    max_backoff: Duration,
+    /// Apply random jitter to the delay.
+    jitter: bool,
+    /// HTTP statuses that are retried.
+    statuses: HashSet<u16>,
+    /// Error codes reported by the API that are retried.
+    codes: HashSet<Code>,
+    /// Use the delay in a `Retry-After` header when present.
+    respect_retry_after: bool,
+    /// Retry requests that time out or fail after they were sent.
+    retry_timeouts: bool,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(2),
+            jitter: true,
+            statuses: [429, 500, 502, 503, 504].iter().copied().collect(),
+            codes: HashSet::new(),
+            respect_retry_after: true,
+            retry_timeouts: false,
+        }
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl RetryPolicy {
+    /// Create a new retry policy with the default settings.
+    pub fn new() -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    /// Set the maximum number of attempts, including the first.
+    ///
+    /// A value of one disables retries.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Set the upper limit for the delay between attempts.
+    pub fn max_backoff(mut self, backoff: Duration) -> Self {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Enable or disable random jitter on the delay between attempts.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Replace the set of HTTP statuses that are retried.
+    pub fn retryable_statuses<I>(mut self, statuses: I) -> Self
+    where
+        I: IntoIterator<Item = u16>,
+    {
+        self.statuses = statuses.into_iter().collect();
+        self
+    }
+
+    /// Retry when the API reports the error code.
+    ///
+    /// Definitive codes such as [Code::InvalidAlreadySeen] are ignored as the
+    /// token may already have been consumed by the first attempt.
+    pub fn retry_on_code(mut self, code: Code) -> Self {
+        if !DEFINITIVE_CODES.contains(&code) {
+            self.codes.insert(code);
+        }
+        self
+    }
+
+    /// Enable or disable honouring the `Retry-After` header.
+    pub fn respect_retry_after(mut self, respect: bool) -> Self {
+        self.respect_retry_after = respect;
+        self
+    }
+
+    /// Enable or disable retrying requests that time out or fail after they
+    /// were sent, and errors of a custom transport.
+    ///
+    /// Disabled by default: the API may have received the request and used
+    /// the token, so the retry is rejected with [Code::InvalidAlreadySeen].
+    pub fn retry_timeouts(mut self, retry: bool) -> Self {
+        self.retry_timeouts = retry;
+        self
+    }
+
+    /// Report if another attempt is allowed after the attempt number given.
+    pub(crate) fn allows_retry(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+
+    /// Delay before retrying a response with the HTTP status, if retryable.
+    pub(crate) fn status_delay(
+        &self,
+        attempt: u32,
+        status: u16,
+        retry_after: Option<&str>,
+    ) -> Option<Duration> {
+        if !self.statuses.contains(&status) {
+            return None;
+        }
+        let retry_after = retry_after
+            .filter(|_| self.respect_retry_after)
+            .and_then(parse_retry_after);
+        match retry_after {
+            Some(delay) if delay > self.max_backoff => None,
+            Some(delay) => Some(delay),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    /// Delay before retrying after an error sending the request, if retryable.
+    pub(crate) fn error_delay(&self, attempt: u32, error: &HcaptchaError) -> Option<Duration> {
+        let sent = match error {
+            #[cfg(feature = "reqwest")]
+            HcaptchaError::Reqwest(e) => e.is_timeout() || e.is_request(),
+            HcaptchaError::Timeout(_) | HcaptchaError::Transport(_) => true,
+            _ => false,
+        };
+        let transient = error.is_connect() || (self.retry_timeouts && sent);
+        transient.then(|| self.backoff(attempt))
+    }
+
+    /// Delay before retrying a response reporting error codes, if retryable.
+    pub(crate) fn codes_delay(
+        &self,
+        attempt: u32,
+        response: &HcaptchaResponse,
+    ) -> Option<Duration> {
+        if response.success() {
+            return None;
+        }
+        let codes = response.error_codes()?;
+        let definitive = codes.iter().any(|c| DEFINITIVE_CODES.contains(c));
+        let retryable = codes.iter().any(|c| self.codes.contains(c));
+        (retryable && !definitive).then(|| self.backoff(attempt))
+    }
+
+    /// Exponential backoff for the attempt, with jitter if enabled.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff);
+        if self.jitter {
+            // Equal jitter: half the backoff plus a random share of the other half.
+            let half = backoff / 2;
+            let random = RandomState::new().build_hasher().finish();
+            half + half.mul_f64(random as f64 / u64::MAX as f64)
+        } else {
+            backoff
+        }
+    }
+}
+
+/// Parse a `Retry-After` header given in seconds or as an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new()
+            .initial_backoff(Duration::from_millis(100))
+            .max_backoff(Duration::from_millis(350))
+            .jitter(false)
+    }
+
+    #[test]
+    fn backoff_grows_exponentially_to_the_maximum() {
+        let policy = policy();
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff(40), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn jitter_stays_within_the_backoff() {
+        let policy = policy().jitter(true);
+        for _ in 0..20 {
+            let delay = policy.backoff(2);
+            assert!(delay >= Duration::from_millis(100));
+            assert!(delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn attempts_are_limited() {
+        let policy = policy().max_attempts(2);
+        assert!(policy.allows_retry(1));
+        assert!(!policy.allows_retry(2));
+        assert!(!RetryPolicy::new().max_attempts(0).allows_retry(1));
+    }
+
+    #[test]
+    fn retryable_status_uses_retry_after() {
+        let policy = policy();
+        assert_eq!(
+            policy
+                .clone()
+                .max_backoff(Duration::from_secs(5))
+                .status_delay(1, 429, Some("3")),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            policy.status_delay(1, 503, None),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy
+                .respect_retry_after(false)
+                .status_delay(1, 429, Some("3")),
+            Some(Duration::from_millis(100))
+        );
+    }
+
+    #[test]
+    fn retry_after_longer_than_max_backoff_is_not_retried() {
+        assert_eq!(policy().status_delay(1, 503, Some("86400")), None);
+    }
+
+    #[test]
+    fn only_connect_errors_are_retried_by_default() {
+        let refused =
+            HcaptchaError::from(std::io::Error::from(std::io::ErrorKind::ConnectionRefused));
+        let timeout = HcaptchaError::Timeout(Duration::from_secs(1));
+        let transport = HcaptchaError::Transport("reset".into());
+
+        let policy = policy();
+        assert_eq!(
+            policy.error_delay(1, &refused),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(policy.error_delay(1, &timeout), None);
+        assert_eq!(policy.error_delay(1, &transport), None);
+
+        let policy = policy.retry_timeouts(true);
+        assert!(policy.error_delay(1, &timeout).is_some());
+        assert!(policy.error_delay(1, &transport).is_some());
+    }
+
+    #[test]
+    fn other_statuses_are_not_retried() {
+        let policy = policy();
+        assert_eq!(policy.status_delay(1, 400, None), None);
+        assert_eq!(policy.status_delay(1, 200, None), None);
+    }
+
+    #[test]
+    fn retry_after_http_date_in_the_past_is_zero() {
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[test]
+    fn definitive_codes_are_never_retried() {
+        let policy = policy()
+            .retry_on_code(Code::InvalidAlreadySeen)
+            .retry_on_code(Code::Unknown("internal-error".to_owned()));
+        let response: HcaptchaResponse = serde_json::from_value(json!({
+            "success": false,
+            "error-codes": ["invalid-or-already-seen-response"],
+        }))
+        .unwrap();
+        assert_eq!(policy.codes_delay(1, &response), None);
+
+        let response: HcaptchaResponse = serde_json::from_value(json!({
+            "success": false,
+            "error-codes": ["internal-error"],
+        }))
+        .unwrap();
+        assert_eq!(
+            policy.codes_delay(1, &response),
+            Some(Duration::from_millis(100))
+        );
+    }
+}
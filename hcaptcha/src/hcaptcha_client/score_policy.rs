@@ -0,0 +1,254 @@
+//! Policy to decide on an enterprise response from its score.
+//!
+//! # Example
+//! Apply a stricter threshold to the login form and deny any response the API
+//! flags as automated.
+//! ```
+//!     use hcaptcha::{HcaptchaResponse, ScorePolicy, Verdict};
+//!
+//!     let policy = ScorePolicy::new()
+//!         .thresholds(0.5, 0.8)
+//!         .action_thresholds("login", 0.3, 0.6)
+//!         .deny_reason("automation");
+//!
+//!     let response: HcaptchaResponse =
+//!         serde_json::from_str(r#"{"success":true,"score":0.4}"#).unwrap();
+//!
+//!     assert_eq!(policy.evaluate(&response, None), Verdict::Allow);
+//!     assert_eq!(policy.evaluate(&response, Some("login")), Verdict::Challenge);
+//! ```
+
+use crate::HcaptchaResponse;
+use std::collections::{HashMap, HashSet};
+
+/// Decision on a response from its score.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Verdict {
+    /// The score is below the challenge threshold.
+    Allow,
+    /// The score is at or above the challenge threshold; ask for further
+    /// proof such as a second factor.
+    Challenge,
+    /// The score is at or above the deny threshold, or a score reason is
+    /// configured to deny.
+    Deny,
+}
+
+/// Thresholds at or above which a score is challenged or denied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Thresholds {
+    challenge: f32,
+    deny: f32,
+}
+
+impl Thresholds {
+    /// Create the thresholds, lowering the challenge threshold to the deny
+    /// threshold if it is above it.
+    fn new(challenge: f32, deny: f32) -> Thresholds {
+        Thresholds {
+            challenge: challenge.min(deny),
+            deny,
+        }
+    }
+}
+
+/// Policy deciding a [Verdict] from the score and score reasons of an
+/// enterprise response.
+///
+/// Scores range from 0.0 (no risk) to 1.0 (confirmed threat). A score at or
+/// above the deny threshold is denied, at or above the challenge threshold
+/// is challenged, and otherwise allowed. By default scores from 0.5 are
+/// challenged and scores from 0.8 denied. Thresholds can be set for a named
+/// action, such as a form, in place of the default thresholds.
+///
+/// A response with a score reason configured to deny is always denied.
+/// A response without a score, or with a score that is not a finite number,
+/// is challenged by default.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+#[derive(Debug, Clone)]
+pub struct ScorePolicy {
+    /// Thresholds applied when no action specific thresholds are set.
+    thresholds: Thresholds,
+    /// Thresholds for named actions.
+    actions: HashMap<String, Thresholds>,
+    /// Score reasons that deny the response.
+    deny_reasons: HashSet<String>,
+    /// Verdict for a response without a score.
+    missing_score: Verdict,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl Default for ScorePolicy {
+    fn default() -> ScorePolicy {
+        ScorePolicy {
+            thresholds: Thresholds::new(0.5, 0.8),
+            actions: HashMap::new(),
+            deny_reasons: HashSet::new(),
+            missing_score: Verdict::Challenge,
+        }
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl ScorePolicy {
+    /// Create a new score policy with the default thresholds.
+    pub fn new() -> ScorePolicy {
+        ScorePolicy::default()
+    }
+
+    /// Set the default thresholds at or above which a score is challenged
+    /// or denied.
+    ///
+    /// A challenge threshold above the deny threshold is lowered to the deny
+    /// threshold.
+    pub fn thresholds(mut self, challenge: f32, deny: f32) -> Self {
+        self.thresholds = Thresholds::new(challenge, deny);
+        self
+    }
+
+    /// Set the thresholds at or above which a score is challenged or denied
+    /// for the named action.
+    ///
+    /// A challenge threshold above the deny threshold is lowered to the deny
+    /// threshold.
+    pub fn action_thresholds(mut self, action: &str, challenge: f32, deny: f32) -> Self {
+        self.actions
+            .insert(action.to_owned(), Thresholds::new(challenge, deny));
+        self
+    }
+
+    /// Deny any response reporting the score reason.
+    pub fn deny_reason(mut self, reason: &str) -> Self {
+        self.deny_reasons.insert(reason.to_owned());
+        self
+    }
+
+    /// Set the verdict for a response without a score, or with a score that
+    /// is not a finite number.
+    pub fn missing_score(mut self, verdict: Verdict) -> Self {
+        self.missing_score = verdict;
+        self
+    }
+
+    /// Decide the verdict for the response.
+    ///
+    /// The thresholds for the action are used if set, otherwise the default
+    /// thresholds apply.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "Evaluate score.", skip(self, response), level = "debug")
+    )]
+    pub fn evaluate(&self, response: &HcaptchaResponse, action: Option<&str>) -> Verdict {
+        let denied_reason = response
+            .score_reason()
+            .is_some_and(|reasons| reasons.iter().any(|r| self.deny_reasons.contains(r)));
+        if denied_reason {
+            return Verdict::Deny;
+        }
+
+        let score = match response.score() {
+            Some(score) if score.is_finite() => score,
+            _ => return self.missing_score,
+        };
+        let thresholds = action
+            .and_then(|action| self.actions.get(action))
+            .unwrap_or(&self.thresholds);
+
+        if score >= thresholds.deny {
+            Verdict::Deny
+        } else if score >= thresholds.challenge {
+            Verdict::Challenge
+        } else {
+            Verdict::Allow
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response(value: serde_json::Value) -> HcaptchaResponse {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn default_thresholds() {
+        let policy = ScorePolicy::new();
+        assert_eq!(
+            policy.evaluate(&response(json!({ "success": true, "score": 0.1 })), None),
+            Verdict::Allow
+        );
+        assert_eq!(
+            policy.evaluate(&response(json!({ "success": true, "score": 0.5 })), None),
+            Verdict::Challenge
+        );
+        assert_eq!(
+            policy.evaluate(&response(json!({ "success": true, "score": 0.9 })), None),
+            Verdict::Deny
+        );
+    }
+
+    #[test]
+    fn action_thresholds_replace_default() {
+        let policy = ScorePolicy::new().action_thresholds("login", 0.2, 0.4);
+        let response = response(json!({ "success": true, "score": 0.45 }));
+        assert_eq!(policy.evaluate(&response, None), Verdict::Allow);
+        assert_eq!(policy.evaluate(&response, Some("login")), Verdict::Deny);
+        assert_eq!(policy.evaluate(&response, Some("signup")), Verdict::Allow);
+    }
+
+    #[test]
+    fn challenge_threshold_is_not_above_deny() {
+        let lowered = Thresholds {
+            challenge: 0.6,
+            deny: 0.6,
+        };
+        assert_eq!(ScorePolicy::new().thresholds(0.9, 0.6).thresholds, lowered);
+        let policy = ScorePolicy::new().action_thresholds("login", 0.9, 0.6);
+        assert_eq!(policy.actions["login"], lowered);
+    }
+
+    #[test]
+    fn deny_reason_overrides_score() {
+        let policy = ScorePolicy::new().deny_reason("automation");
+        let response = response(json!({
+            "success": true,
+            "score": 0.0,
+            "score_reason": ["automation", "safe"],
+        }));
+        assert_eq!(policy.evaluate(&response, None), Verdict::Deny);
+    }
+
+    #[test]
+    fn missing_score_verdict() {
+        let response = response(json!({ "success": true }));
+        assert_eq!(
+            ScorePolicy::new().evaluate(&response, None),
+            Verdict::Challenge
+        );
+        assert_eq!(
+            ScorePolicy::new()
+                .missing_score(Verdict::Allow)
+                .evaluate(&response, None),
+            Verdict::Allow
+        );
+    }
+
+    #[test]
+    fn non_finite_score_is_missing() {
+        // Too large for an f32, the score is read as infinity.
+        let response = response(json!({ "success": true, "score": 1e40 }));
+        assert_eq!(response.score(), Some(f32::INFINITY));
+        assert_eq!(
+            ScorePolicy::new()
+                .missing_score(Verdict::Allow)
+                .evaluate(&response, None),
+            Verdict::Allow
+        );
+    }
+}
@@ -0,0 +1,305 @@
+//! Secrets fetched at verification time from a secret store.
+//!
+//! A [SecretProvider] is consulted by
+//! [verify_captcha](crate::HcaptchaClient::verify_captcha) for the secret of
+//! each verification, so the secret can live in a parameter store or vault
+//! rather than in the configuration of the application.
+//! [CachedSecretProvider] keeps the fetched secrets for a time to live, and
+//! [Secrets] holds a secondary secret to use while the secret is rotated.
+//!
+//! # Example
+//! Fetch the secret from a store at most once every five minutes.
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::{async_trait, HcaptchaCaptcha, HcaptchaClient, HcaptchaError};
+//!     use hcaptcha::{CachedSecretProvider, SecretProvider, Secrets};
+//!     use std::time::Duration;
+//!
+//!     #[derive(Debug)]
+//!     struct ParameterStore;
+//!
+//!     #[async_trait]
+//!     impl SecretProvider for ParameterStore {
+//!         async fn secrets(&self) -> Result<Secrets, HcaptchaError> {
+//!             // Read the current and previous secret from your store
+//!             Secrets::new("0x123456789abcde0f123456789abcdef012345678")?
+//!                 .secondary("0x0000000123456789abcdefABCDEF000000000000")
+//!         }
+//!     }
+//!
+//!     let client = HcaptchaClient::builder()
+//!         .secret_provider(CachedSecretProvider::new(
+//!             ParameterStore,
+//!             Duration::from_secs(300),
+//!         ))
+//!         .build()?;
+//!
+//! #   let token = "10000000-aaaa-bbbb-cccc-000000000001";
+//!     let response = client.verify_captcha(HcaptchaCaptcha::new(token)?).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::domain::HcaptchaSecret;
+use crate::HcaptchaError;
+use async_trait::async_trait;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex as AsyncMutex;
+
+/// Provide the secrets used to verify captchas.
+///
+/// Errors other than [HcaptchaError::Codes] raised by the secret store can
+/// be reported with [HcaptchaError::SecretProvider].
+#[async_trait]
+pub trait SecretProvider: fmt::Debug + Send + Sync {
+    /// Fetch the current secrets.
+    async fn secrets(&self) -> Result<Secrets, HcaptchaError>;
+}
+
+/// The primary secret and an optional secondary secret.
+///
+/// Captchas are verified with the primary secret. If the API rejects the
+/// primary secret with [Code::InvalidSecret](crate::Code::InvalidSecret)
+/// the verification is repeated once with the secondary secret, so the
+/// secret can be rotated without failing verifications while the new
+/// secret propagates.
+///
+/// The secrets also serve as a [SecretProvider] returning fixed secrets.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Clone)]
+pub struct Secrets {
+    /// Secret used first.
+    primary: HcaptchaSecret,
+    /// Secret used if the API rejects the primary secret.
+    secondary: Option<HcaptchaSecret>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl Secrets {
+    /// Create the secrets with the primary secret.
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError::Codes] if the secret is invalid.
+    pub fn new(primary: &str) -> Result<Secrets, HcaptchaError> {
+        Ok(Secrets {
+            primary: HcaptchaSecret::parse(primary.to_owned())?,
+            secondary: None,
+        })
+    }
+
+    /// Set the secondary secret used while the secret is rotated.
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError::Codes] if the secret is invalid.
+    pub fn secondary(mut self, secondary: &str) -> Result<Self, HcaptchaError> {
+        self.secondary = Some(HcaptchaSecret::parse(secondary.to_owned())?);
+        Ok(self)
+    }
+
+    /// Split into the primary and secondary secret.
+    pub(crate) fn into_parts(self) -> (HcaptchaSecret, Option<HcaptchaSecret>) {
+        (self.primary, self.secondary)
+    }
+}
+
+#[async_trait]
+impl SecretProvider for Secrets {
+    async fn secrets(&self) -> Result<Secrets, HcaptchaError> {
+        Ok(self.clone())
+    }
+}
+
+/// Cache the secrets of a [SecretProvider] for a time to live.
+///
+/// The provider is consulted again once the secrets are older than the
+/// time to live. If the refresh fails the expired secrets continue to be
+/// used for another time to live before the refresh is retried, so an
+/// outage of the secret store neither stops verifications nor is hit by
+/// every verification. Concurrent verifications wait for a single refresh.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug)]
+pub struct CachedSecretProvider<P> {
+    /// Provider of the secrets.
+    provider: P,
+    /// Time the secrets are used before they are refreshed.
+    ttl: Duration,
+    /// Secrets and the time they were fetched or last failed to refresh.
+    cached: Mutex<Option<(Instant, Secrets)>>,
+    /// Held while the secrets are refreshed.
+    refresh: AsyncMutex<()>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl<P> CachedSecretProvider<P>
+where
+    P: SecretProvider,
+{
+    /// Cache the secrets of the provider for the time to live.
+    pub fn new(provider: P, ttl: Duration) -> CachedSecretProvider<P> {
+        CachedSecretProvider {
+            provider,
+            ttl,
+            cached: Mutex::new(None),
+            refresh: AsyncMutex::new(()),
+        }
+    }
+
+    /// Get the cached secrets and report if they have expired.
+    fn cached(&self) -> Option<(Secrets, bool)> {
+        let cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+        cached
+            .as_ref()
+            .map(|(fetched, secrets)| (secrets.clone(), fetched.elapsed() >= self.ttl))
+    }
+}
+
+#[async_trait]
+impl<P> SecretProvider for CachedSecretProvider<P>
+where
+    P: SecretProvider,
+{
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "Get cached secrets.", skip(self), level = "debug")
+    )]
+    async fn secrets(&self) -> Result<Secrets, HcaptchaError> {
+        if let Some((secrets, false)) = self.cached() {
+            return Ok(secrets);
+        }
+        let _refresh = self.refresh.lock().await;
+        // Another verification may have refreshed the secrets while waiting
+        let stale = match self.cached() {
+            Some((secrets, false)) => return Ok(secrets),
+            Some((secrets, true)) => Some(secrets),
+            None => None,
+        };
+        let (secrets, result) = match self.provider.secrets().await {
+            Ok(secrets) => {
+                #[cfg(feature = "trace")]
+                tracing::debug!("Secrets refreshed");
+                (secrets.clone(), Ok(secrets))
+            }
+            Err(e) => match stale {
+                Some(secrets) => {
+                    #[cfg(feature = "trace")]
+                    tracing::debug!("Refresh of secrets failed, using expired secrets: {}", e);
+                    (secrets.clone(), Ok(secrets))
+                }
+                None => return Err(e),
+            },
+        };
+        *self.cached.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), secrets));
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const SECRET: &str = "0x123456789abcde0f123456789abcdef012345678";
+    const OTHER_SECRET: &str = "0x0000000123456789abcdefABCDEF000000000000";
+
+    /// Provider counting the fetches and failing after the first.
+    #[derive(Debug, Clone, Default)]
+    struct CountingProvider {
+        fetches: Arc<AtomicUsize>,
+        fail_after_first: bool,
+        delay: Duration,
+    }
+
+    #[async_trait]
+    impl SecretProvider for CountingProvider {
+        async fn secrets(&self) -> Result<Secrets, HcaptchaError> {
+            tokio::time::sleep(self.delay).await;
+            let fetches = self.fetches.fetch_add(1, Ordering::SeqCst);
+            if self.fail_after_first && fetches > 0 {
+                return Err(HcaptchaError::SecretProvider("store unavailable".into()));
+            }
+            Secrets::new(SECRET)
+        }
+    }
+
+    #[test]
+    fn secrets_are_validated() {
+        assert!(Secrets::new("").is_err());
+        assert!(Secrets::new(SECRET).unwrap().secondary("").is_err());
+
+        let (primary, secondary) = Secrets::new(SECRET)
+            .unwrap()
+            .secondary(OTHER_SECRET)
+            .unwrap()
+            .into_parts();
+        assert_eq!(primary.expose(), SECRET);
+        assert_eq!(secondary.unwrap().expose(), OTHER_SECRET);
+    }
+
+    #[tokio::test]
+    async fn secrets_are_cached_for_ttl() {
+        let provider = CountingProvider::default();
+        let cached = CachedSecretProvider::new(provider.clone(), Duration::from_secs(60));
+        cached.secrets().await.unwrap();
+        cached.secrets().await.unwrap();
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 1);
+
+        let cached = CachedSecretProvider::new(provider.clone(), Duration::ZERO);
+        cached.secrets().await.unwrap();
+        cached.secrets().await.unwrap();
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn expired_secrets_are_used_if_refresh_fails() {
+        let provider = CountingProvider {
+            fail_after_first: true,
+            ..CountingProvider::default()
+        };
+        let cached = CachedSecretProvider::new(provider.clone(), Duration::ZERO);
+        cached.secrets().await.unwrap();
+        let (primary, _) = cached.secrets().await.unwrap().into_parts();
+        assert_eq!(primary.expose(), SECRET);
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 2);
+
+        let cached = CachedSecretProvider::new(provider, Duration::ZERO);
+        assert!(matches!(
+            cached.secrets().await,
+            Err(HcaptchaError::SecretProvider(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn failed_refresh_is_not_retried_within_ttl() {
+        let provider = CountingProvider {
+            fail_after_first: true,
+            ..CountingProvider::default()
+        };
+        let cached = CachedSecretProvider::new(provider.clone(), Duration::from_millis(200));
+        cached.secrets().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        cached.secrets().await.unwrap();
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 2);
+
+        cached.secrets().await.unwrap();
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_refreshes_fetch_once() {
+        let provider = CountingProvider {
+            delay: Duration::from_millis(50),
+            ..CountingProvider::default()
+        };
+        let cached = CachedSecretProvider::new(provider.clone(), Duration::from_secs(60));
+        let (a, b) = tokio::join!(cached.secrets(), cached.secrets());
+        a.unwrap();
+        b.unwrap();
+        assert_eq!(provider.fetches.load(Ordering::SeqCst), 1);
+    }
+}
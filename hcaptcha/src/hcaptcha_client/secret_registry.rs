@@ -0,0 +1,267 @@
+//! Registry of the secrets of many sites, keyed by sitekey.
+//!
+//! # Example
+//! Verify captchas for two sites with one client.
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::{HcaptchaCaptcha, HcaptchaClient, SecretRegistry};
+//!
+//!     let registry = SecretRegistry::new()
+//!         .insert(
+//!             "10000000-ffff-ffff-ffff-000000000001",
+//!             "0x123456789abcde0f123456789abcdef012345678",
+//!         )?
+//!         .insert(
+//!             "20000000-ffff-ffff-ffff-000000000002",
+//!             "0x0000000123456789abcdefABCDEF000000000000",
+//!         )?;
+//!     let client = HcaptchaClient::builder()
+//!         .secret_registry(registry)
+//!         .build()?;
+//!
+//! #   let token = "10000000-aaaa-bbbb-cccc-000000000001";
+//!     let captcha = HcaptchaCaptcha::new(token)?
+//!         .set_sitekey("20000000-ffff-ffff-ffff-000000000002")?;
+//!     let response = client.verify_captcha(captcha).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::domain::{HcaptchaSecret, HcaptchaSitekey};
+use crate::{Code, HcaptchaError};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+/// Function looking up the secret of a sitekey missing from the registry.
+type SecretLookup = dyn Fn(&str) -> Option<String> + Send + Sync;
+
+/// Secrets of many sites, keyed by the sitekey of each site.
+///
+/// The secrets are loaded from configuration with
+/// [insert](SecretRegistry::insert) or [from_pairs](SecretRegistry::from_pairs),
+/// from an environment variable with [from_env](SecretRegistry::from_env), or
+/// looked up on demand with a [lookup](SecretRegistry::lookup) function.
+///
+/// Set the registry on the [HcaptchaClientBuilder](crate::HcaptchaClientBuilder)
+/// and verify captchas with
+/// [verify_captcha](crate::HcaptchaClient::verify_captcha). A captcha whose
+/// sitekey is not registered is rejected with [Code::UnknownSitekey].
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Default, Clone)]
+pub struct SecretRegistry {
+    /// Secrets keyed by sitekey.
+    secrets: HashMap<String, HcaptchaSecret>,
+    /// Function looking up the secret of a sitekey that is not registered.
+    lookup: Option<Arc<SecretLookup>>,
+}
+
+impl fmt::Debug for SecretRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecretRegistry")
+            .field("sitekeys", &self.secrets.keys().collect::<Vec<_>>())
+            .field("lookup", &self.lookup.is_some())
+            .finish()
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl SecretRegistry {
+    /// Create an empty registry.
+    pub fn new() -> SecretRegistry {
+        SecretRegistry::default()
+    }
+
+    /// Register the secret of the sitekey.
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError::Codes] if the sitekey or the secret is invalid.
+    pub fn insert(mut self, sitekey: &str, secret: &str) -> Result<Self, HcaptchaError> {
+        let sitekey = HcaptchaSitekey::parse(sitekey.to_owned())?;
+        let secret = HcaptchaSecret::parse(secret.to_owned())?;
+        self.secrets.insert(sitekey.to_string(), secret);
+        Ok(self)
+    }
+
+    /// Create a registry from sitekey and secret pairs, such as a table
+    /// read from a configuration file.
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError::Codes] if a sitekey or secret is invalid.
+    pub fn from_pairs<I, K, V>(pairs: I) -> Result<SecretRegistry, HcaptchaError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<str>,
+    {
+        pairs
+            .into_iter()
+            .try_fold(SecretRegistry::new(), |registry, (sitekey, secret)| {
+                registry.insert(sitekey.as_ref(), secret.as_ref())
+            })
+    }
+
+    /// Create a registry from the environment variable.
+    ///
+    /// The variable holds `sitekey=secret` pairs separated by commas or
+    /// whitespace, for example
+    /// `HCAPTCHA_SECRETS="10000000-ffff-ffff-ffff-000000000001=0x1234..."`.
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError::Io] if the variable is not set or a pair is
+    /// malformed, and [HcaptchaError::Codes] if a sitekey or secret is
+    /// invalid.
+    pub fn from_env(name: &str) -> Result<SecretRegistry, HcaptchaError> {
+        // The error is not formatted as it holds a value that is not unicode
+        let value = std::env::var(name)
+            .map(Zeroizing::new)
+            .map_err(|e| match e {
+                std::env::VarError::NotPresent => {
+                    std::io::Error::new(std::io::ErrorKind::NotFound, format!("{}: not set", name))
+                }
+                std::env::VarError::NotUnicode(_) => std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{}: not valid unicode", name),
+                ),
+            })?;
+        let pairs = value
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                pair.split_once('=').ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("{}: expected sitekey=secret pairs", name),
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        SecretRegistry::from_pairs(pairs)
+    }
+
+    /// Look up the secret of a sitekey that is not registered with the
+    /// function.
+    ///
+    /// The function is called with the sitekey on every verification for
+    /// that sitekey and returns the secret, or `None` if the sitekey is
+    /// unknown.
+    pub fn lookup<F>(mut self, lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.lookup = Some(Arc::new(lookup));
+        self
+    }
+
+    /// Check if a secret is registered for the sitekey.
+    ///
+    /// Secrets available from the lookup function are not checked.
+    pub fn contains(&self, sitekey: &str) -> bool {
+        self.secrets.contains_key(sitekey)
+    }
+
+    /// Get the secret of the sitekey.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(name = "Get secret for sitekey.", skip(self), level = "debug")
+    )]
+    pub(crate) fn secret(&self, sitekey: &str) -> Result<HcaptchaSecret, HcaptchaError> {
+        if let Some(secret) = self.secrets.get(sitekey) {
+            return Ok(secret.clone());
+        }
+        match self.lookup.as_ref().and_then(|lookup| lookup(sitekey)) {
+            Some(secret) => HcaptchaSecret::parse(secret),
+            None => {
+                #[cfg(feature = "trace")]
+                tracing::debug!("{}", Code::UnknownSitekey);
+                let mut codes = HashSet::new();
+                codes.insert(Code::UnknownSitekey);
+                Err(HcaptchaError::Codes(codes))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SITEKEY: &str = "10000000-ffff-ffff-ffff-000000000001";
+    const OTHER_SITEKEY: &str = "20000000-ffff-ffff-ffff-000000000002";
+    const SECRET: &str = "0x123456789abcde0f123456789abcdef012345678";
+
+    fn is_unknown(result: Result<HcaptchaSecret, HcaptchaError>) -> bool {
+        matches!(result, Err(HcaptchaError::Codes(codes)) if codes.contains(&Code::UnknownSitekey))
+    }
+
+    #[test]
+    fn secret_is_found_by_sitekey() {
+        let registry = SecretRegistry::new().insert(SITEKEY, SECRET).unwrap();
+        assert!(registry.contains(SITEKEY));
+        assert_eq!(registry.secret(SITEKEY).unwrap().expose(), SECRET);
+        assert!(is_unknown(registry.secret(OTHER_SITEKEY)));
+    }
+
+    #[test]
+    fn invalid_pairs_are_rejected() {
+        assert!(SecretRegistry::new()
+            .insert("not-a-sitekey", SECRET)
+            .is_err());
+        assert!(SecretRegistry::new().insert(SITEKEY, "").is_err());
+        assert!(SecretRegistry::from_pairs([(SITEKEY, SECRET), (OTHER_SITEKEY, "")]).is_err());
+    }
+
+    #[test]
+    fn secrets_are_read_from_env() {
+        let name = "HCAPTCHA_TEST_SECRET_REGISTRY";
+        std::env::set_var(
+            name,
+            format!("{}={}, {}={}", SITEKEY, SECRET, OTHER_SITEKEY, SECRET),
+        );
+        let registry = SecretRegistry::from_env(name).unwrap();
+        std::env::remove_var(name);
+
+        assert!(registry.contains(SITEKEY));
+        assert!(registry.contains(OTHER_SITEKEY));
+        assert!(SecretRegistry::from_env(name).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn env_error_does_not_contain_the_value() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let name = "HCAPTCHA_TEST_SECRET_REGISTRY_NOT_UNICODE";
+        let mut value = format!("{}={}", SITEKEY, SECRET).into_bytes();
+        value.push(0xff);
+        std::env::set_var(name, std::ffi::OsString::from_vec(value));
+        let err = SecretRegistry::from_env(name).unwrap_err();
+        std::env::remove_var(name);
+
+        assert_eq!(
+            err.to_string(),
+            "HCAPTCHA_TEST_SECRET_REGISTRY_NOT_UNICODE: not valid unicode"
+        );
+    }
+
+    #[test]
+    fn lookup_is_consulted_for_unregistered_sitekey() {
+        let registry = SecretRegistry::new()
+            .lookup(|sitekey| (sitekey == OTHER_SITEKEY).then(|| SECRET.to_owned()));
+        assert_eq!(registry.secret(OTHER_SITEKEY).unwrap().expose(), SECRET);
+        assert!(is_unknown(registry.secret(SITEKEY)));
+    }
+
+    #[test]
+    fn debug_output_hides_secrets() {
+        let registry = SecretRegistry::new().insert(SITEKEY, SECRET).unwrap();
+        let debug = format!("{:?}", registry);
+        assert!(debug.contains(SITEKEY));
+        assert!(!debug.contains(SECRET));
+    }
+}
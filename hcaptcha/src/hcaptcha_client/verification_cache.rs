@@ -0,0 +1,183 @@
+//! Cache of successful verifications to verify a token more than once.
+//!
+//! A token can only be verified once with the API. A flow that verifies the
+//! token at more than one step, such as validating a form and then
+//! committing it, receives
+//! [Code::InvalidAlreadySeen](crate::Code::InvalidAlreadySeen) at the second
+//! step. A [VerificationCache] keeps the response to a successful
+//! verification for a short time and returns it when the same token is
+//! verified again with the same secret.
+//!
+//! # Example
+//! ```
+//! # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::{HcaptchaClient, VerificationCache};
+//!     use std::time::Duration;
+//!
+//!     let cache = VerificationCache::new(Duration::from_secs(30));
+//!     let client = HcaptchaClient::builder()
+//!         .verification_cache(cache.clone())
+//!         .build()?;
+//!
+//!     // verify the token at each step of the flow with the client
+//!
+//!     println!("{} hits, {} misses", cache.hits(), cache.misses());
+//! # Ok(())
+//! # }
+//! ```
+
+use super::TokenHash;
+use crate::HcaptchaResponse;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Responses to successful verifications, keyed by the hash of the token
+/// and secret.
+///
+/// Only successful verifications are cached. A cached response is checked
+/// again against the [VerificationPolicy](crate::VerificationPolicy) of the
+/// client, so it is rejected once older than the maximum age.
+///
+/// Clones share the cached responses and counters, so a clone kept by the
+/// application reports the hits and misses of the client.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Clone)]
+pub struct VerificationCache {
+    /// State shared between clones.
+    inner: Arc<Inner>,
+}
+
+/// State of the cache.
+#[derive(Debug)]
+struct Inner {
+    /// Time a response is kept.
+    ttl: Duration,
+    /// Cached responses.
+    entries: Mutex<Entries>,
+    /// Number of verifications answered from the cache.
+    hits: AtomicU64,
+    /// Number of verifications not found in the cache.
+    misses: AtomicU64,
+}
+
+/// Cached responses and the order they expire.
+#[derive(Debug, Default)]
+struct Entries {
+    /// Responses and the time they were cached.
+    responses: HashMap<TokenHash, (Instant, HcaptchaResponse)>,
+    /// Time each response was cached, oldest first.
+    expiry: VecDeque<(Instant, TokenHash)>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl VerificationCache {
+    /// Keep the responses for the time to live.
+    pub fn new(ttl: Duration) -> VerificationCache {
+        VerificationCache {
+            inner: Arc::new(Inner {
+                ttl,
+                entries: Mutex::new(Entries::default()),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Get the number of verifications answered from the cache.
+    pub fn hits(&self) -> u64 {
+        self.inner.hits.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of verifications not found in the cache.
+    pub fn misses(&self) -> u64 {
+        self.inner.misses.load(Ordering::Relaxed)
+    }
+
+    /// Get the number of responses cached.
+    pub fn len(&self) -> usize {
+        self.entries().responses.len()
+    }
+
+    /// Report if no responses are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the cached response for the key, counting the hit or miss.
+    pub(crate) fn get(&self, key: &TokenHash) -> Option<HcaptchaResponse> {
+        let response = self
+            .entries()
+            .responses
+            .get(key)
+            .map(|(_, response)| response.clone());
+        let counter = match response {
+            Some(_) => &self.inner.hits,
+            None => &self.inner.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        response
+    }
+
+    /// Cache the response for the key.
+    pub(crate) fn insert(&self, key: TokenHash, response: HcaptchaResponse) {
+        let now = Instant::now();
+        let mut entries = self.entries();
+        entries.responses.insert(key, (now, response));
+        entries.expiry.push_back((now, key));
+    }
+
+    /// Lock the entries, dropping the expired responses.
+    fn entries(&self) -> MutexGuard<'_, Entries> {
+        let mut entries = self.inner.entries.lock().unwrap_or_else(|e| e.into_inner());
+        while let Some((cached, key)) = entries.expiry.front().copied() {
+            if cached.elapsed() < self.inner.ttl {
+                break;
+            }
+            entries.expiry.pop_front();
+            // The key may have been cached again since.
+            if matches!(entries.responses.get(&key), Some((at, _)) if *at == cached) {
+                entries.responses.remove(&key);
+            }
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn response() -> HcaptchaResponse {
+        serde_json::from_value(json!({ "success": true, "hostname": "example.com" })).unwrap()
+    }
+
+    #[test]
+    fn responses_are_cached_and_counted() {
+        let cache = VerificationCache::new(Duration::from_secs(60));
+        let key = TokenHash::with_secret("token", "secret");
+        assert!(cache.get(&key).is_none());
+
+        cache.clone().insert(key, response());
+        let cached = cache.get(&key).unwrap();
+        assert_eq!(cached.hostname().as_deref(), Some("example.com"));
+        assert!(cache
+            .get(&TokenHash::with_secret("token", "other"))
+            .is_none());
+
+        assert_eq!(cache.hits(), 1);
+        assert_eq!(cache.misses(), 2);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn responses_expire_after_ttl() {
+        let cache = VerificationCache::new(Duration::ZERO);
+        let key = TokenHash::with_secret("token", "secret");
+        cache.insert(key, response());
+        assert!(cache.get(&key).is_none());
+        assert!(cache.is_empty());
+    }
+}
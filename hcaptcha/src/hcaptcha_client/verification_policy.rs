@@ -0,0 +1,265 @@
+//! Policy applied to a successful response from the siteverify API.
+//!
+//! # Example
+//! Accept tokens solved on the site or its subdomains for the expected
+//! sitekey.
+//! ```
+//! # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::{HcaptchaClient, VerificationPolicy};
+//!
+//!     let policy = VerificationPolicy::new()
+//!         .allow_hostname("example.com")
+//!         .allow_hostname("*.example.com")
+//!         .sitekey("10000000-ffff-ffff-ffff-000000000001");
+//!
+//!     let client = HcaptchaClient::builder().policy(policy).build()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::hcaptcha_form::HcaptchaForm;
+use crate::{Code, HcaptchaError, HcaptchaResponse};
+use std::collections::HashSet;
+#[cfg(feature = "chrono")]
+use std::time::Duration;
+
+/// Policy checking a successful response against the expected hostname,
+/// challenge age and sitekey.
+///
+/// Violations are reported as [HcaptchaError::Codes] containing
+/// [Code::HostnameMismatch], [Code::ChallengeTooOld] or
+/// [Code::SitekeyMismatch]. All violations found are reported together.
+///
+/// By default nothing is checked.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Default, Clone)]
+pub struct VerificationPolicy {
+    /// Hostnames, or `*.` wildcard patterns, the captcha may be solved on.
+    hostnames: Vec<String>,
+    /// Maximum time allowed since the captcha was solved.
+    #[cfg(feature = "chrono")]
+    max_age: Option<Duration>,
+    /// Sitekey the captcha must be solved for.
+    sitekey: Option<String>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl VerificationPolicy {
+    /// Create a new policy that checks nothing.
+    pub fn new() -> VerificationPolicy {
+        VerificationPolicy::default()
+    }
+
+    /// Allow captchas solved on the hostname.
+    ///
+    /// A pattern starting `*.` allows any subdomain of the domain that
+    /// follows, but not the domain itself. Hostnames are compared without
+    /// regard to case. Once a hostname is allowed, a response from any other
+    /// hostname, or without a hostname, is rejected.
+    pub fn allow_hostname(mut self, hostname: &str) -> Self {
+        self.hostnames
+            .push(hostname.trim_end_matches('.').to_lowercase());
+        self
+    }
+
+    /// Set the maximum time allowed since the captcha was solved.
+    ///
+    /// A response without a valid challenge timestamp is rejected.
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Require the captcha to be solved for the sitekey.
+    ///
+    /// A request without a sitekey is sent with this sitekey, so the API
+    /// checks the token was issued for it. A request with a different
+    /// sitekey is rejected without calling the API. With the `enterprise`
+    /// feature the sitekey returned in the response is checked as well.
+    pub fn sitekey(mut self, sitekey: &str) -> Self {
+        self.sitekey = Some(sitekey.to_owned());
+        self
+    }
+
+    /// Bind the form to the expected sitekey before it is sent.
+    pub(crate) fn bind_sitekey(&self, form: &mut HcaptchaForm) -> Result<(), HcaptchaError> {
+        let expected = match &self.sitekey {
+            Some(expected) => expected,
+            None => return Ok(()),
+        };
+        match form.sitekey() {
+            Some(sitekey) if sitekey != expected => Err(violation(Code::SitekeyMismatch)),
+            Some(_) => Ok(()),
+            None => {
+                form.set_sitekey(expected);
+                Ok(())
+            }
+        }
+    }
+
+    /// Check the successful response complies with the policy.
+    pub(crate) fn check(&self, response: &HcaptchaResponse) -> Result<(), HcaptchaError> {
+        let mut codes = HashSet::new();
+
+        if !self.hostnames.is_empty() {
+            let allowed = response.hostname().is_some_and(|hostname| {
+                self.hostnames
+                    .iter()
+                    .any(|pattern| hostname_matches(pattern, &hostname))
+            });
+            if !allowed {
+                codes.insert(Code::HostnameMismatch);
+            }
+        }
+
+        #[cfg(feature = "chrono")]
+        if let Some(max_age) = self.max_age {
+            let too_old = match (
+                response.age(chrono::Utc::now()),
+                chrono::TimeDelta::from_std(max_age),
+            ) {
+                (Some(age), Ok(max_age)) => age > max_age,
+                (Some(_), Err(_)) => false,
+                (None, _) => true,
+            };
+            if too_old {
+                codes.insert(Code::ChallengeTooOld);
+            }
+        }
+
+        #[cfg(feature = "enterprise")]
+        if let (Some(expected), Some(sitekey)) = (&self.sitekey, response.sitekey()) {
+            if &sitekey != expected {
+                codes.insert(Code::SitekeyMismatch);
+            }
+        }
+
+        if codes.is_empty() {
+            Ok(())
+        } else {
+            #[cfg(feature = "trace")]
+            tracing::debug!("The response violates the policy: {:?}", codes);
+            Err(HcaptchaError::Codes(codes))
+        }
+    }
+}
+
+/// Match the hostname against a hostname or `*.` wildcard pattern.
+fn hostname_matches(pattern: &str, hostname: &str) -> bool {
+    let hostname = hostname.trim_end_matches('.').to_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => hostname
+            .strip_suffix(domain)
+            .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+        None => hostname == pattern,
+    }
+}
+
+fn violation(code: Code) -> HcaptchaError {
+    let mut codes = HashSet::new();
+    codes.insert(code);
+    HcaptchaError::Codes(codes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use claims::{assert_err, assert_ok};
+    use serde_json::json;
+
+    fn response(value: serde_json::Value) -> HcaptchaResponse {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn codes(result: Result<(), HcaptchaError>) -> HashSet<Code> {
+        match result {
+            Err(HcaptchaError::Codes(codes)) => codes,
+            r => panic!("expected error codes, got {:?}", r),
+        }
+    }
+
+    #[test]
+    fn hostname_patterns() {
+        assert!(hostname_matches("example.com", "Example.COM"));
+        assert!(hostname_matches("example.com", "example.com."));
+        assert!(!hostname_matches("example.com", "www.example.com"));
+        assert!(hostname_matches("*.example.com", "www.example.com"));
+        assert!(hostname_matches("*.example.com", "a.b.example.com"));
+        assert!(!hostname_matches("*.example.com", "example.com"));
+        assert!(!hostname_matches("*.example.com", "badexample.com"));
+    }
+
+    #[test]
+    fn empty_policy_accepts_response() {
+        assert_ok!(VerificationPolicy::new().check(&response(json!({ "success": true }))));
+    }
+
+    #[test]
+    fn hostname_is_enforced() {
+        let policy = VerificationPolicy::new()
+            .allow_hostname("example.com")
+            .allow_hostname("*.example.com");
+        assert_ok!(policy.check(&response(json!({
+            "success": true,
+            "hostname": "app.example.com",
+        }))));
+        let codes = codes(policy.check(&response(json!({
+            "success": true,
+            "hostname": "evil.com",
+        }))));
+        assert!(codes.contains(&Code::HostnameMismatch));
+        assert_err!(policy.check(&response(json!({ "success": true }))));
+    }
+
+    #[cfg(feature = "enterprise")]
+    #[test]
+    fn sitekey_is_enforced() {
+        let policy = VerificationPolicy::new().sitekey("expected-key");
+        assert_ok!(policy.check(&response(json!({ "success": true }))));
+        assert_ok!(policy.check(&response(json!({
+            "success": true,
+            "sitekey": "expected-key",
+        }))));
+        let codes = codes(policy.check(&response(json!({
+            "success": true,
+            "sitekey": "other-key",
+        }))));
+        assert!(codes.contains(&Code::SitekeyMismatch));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn max_age_is_enforced() {
+        use chrono::{TimeDelta, Utc};
+
+        let policy = VerificationPolicy::new().max_age(Duration::from_secs(120));
+        let recent = (Utc::now() - TimeDelta::try_seconds(30).unwrap()).to_rfc3339();
+        let old = (Utc::now() - TimeDelta::try_minutes(10).unwrap()).to_rfc3339();
+        assert_ok!(policy.check(&response(json!({
+            "success": true,
+            "challenge_ts": recent,
+        }))));
+        let codes = codes(policy.check(&response(json!({
+            "success": true,
+            "challenge_ts": old,
+        }))));
+        assert!(codes.contains(&Code::ChallengeTooOld));
+        assert_err!(policy.check(&response(json!({ "success": true }))));
+    }
+
+    #[cfg(feature = "enterprise")]
+    #[test]
+    fn violations_are_reported_together() {
+        let policy = VerificationPolicy::new()
+            .allow_hostname("example.com")
+            .sitekey("expected-key");
+        let codes = codes(policy.check(&response(json!({
+            "success": true,
+            "hostname": "evil.com",
+            "sitekey": "other-key",
+        }))));
+        assert_eq!(codes.len(), 2);
+    }
+}
@@ -4,6 +4,7 @@ use serde::{Deserialize, Deserializer};
 use std::collections::HashSet;
 use std::fmt;
 use std::io;
+use std::time::Duration;
 use thiserror::Error;
 
 /// The error type for hcaptcha.
@@ -16,6 +17,8 @@ pub enum HcaptchaError {
     #[error("{0:?}")]
     Codes(HashSet<Code>),
     /// Error returned by reqwest
+    #[cfg(feature = "reqwest")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
     #[error("{0}")]
     Reqwest(#[from] reqwest::Error),
     /// Error returned by io
@@ -33,6 +36,62 @@ pub enum HcaptchaError {
     /// Error returned by url parser
     #[error("{0}")]
     Url(#[from] url::ParseError),
+    /// Error returned by ipnet when parsing a network of trusted proxies
+    #[error("{0}")]
+    IpNet(#[from] ipnet::AddrParseError),
+    /// The API responded with an HTTP status other than success.
+    #[error("The API responded with HTTP status {status}: {body_excerpt}")]
+    HttpStatus {
+        /// The HTTP status code of the response.
+        status: u16,
+        /// The leading characters of the response body.
+        body_excerpt: String,
+    },
+    /// The body of the API response could not be decoded.
+    #[error("The API response with content type {content_type:?} could not be decoded: {source}")]
+    Decode {
+        /// The content type reported by the response, if any.
+        content_type: Option<String>,
+        /// The error decoding the body.
+        source: serde_json::Error,
+    },
+    /// The verification did not complete within the configured time.
+    #[error("Verification timed out after {0:?}")]
+    Timeout(Duration),
+    /// Error returned by a custom [HcaptchaTransport](crate::HcaptchaTransport)
+    #[error("Transport error: {0}")]
+    Transport(Box<dyn std::error::Error + Send + Sync>),
+    /// Error returned by a custom [SecretProvider](crate::SecretProvider)
+    #[error("Secret provider error: {0}")]
+    SecretProvider(Box<dyn std::error::Error + Send + Sync>),
+    /// Error returned by a custom [ReplayGuard](crate::ReplayGuard)
+    #[error("Replay guard error: {0}")]
+    ReplayGuard(Box<dyn std::error::Error + Send + Sync>),
+    /// The verification exceeds the [RateLimit](crate::RateLimit) of the client.
+    #[error("Verification rejected by the client rate limit")]
+    RateLimited,
+    /// The [CircuitBreaker](crate::CircuitBreaker) of the client is open and
+    /// the API is not called.
+    #[error("Verification rejected while the circuit breaker is open")]
+    CircuitOpen,
+}
+
+impl HcaptchaError {
+    /// Report if the error is a failure to connect to the API, so the
+    /// request was not received by the API.
+    pub(crate) fn is_connect(&self) -> bool {
+        match self {
+            #[cfg(feature = "reqwest")]
+            HcaptchaError::Reqwest(e) => e.is_connect(),
+            HcaptchaError::Io(e) => matches!(
+                e.kind(),
+                io::ErrorKind::ConnectionRefused
+                    | io::ErrorKind::AddrNotAvailable
+                    | io::ErrorKind::NotConnected
+            ),
+            _ => false,
+        }
+    }
 }
 
 /// Error code mapping for the error responses from the hcaptcha API.
@@ -74,6 +133,14 @@ pub enum Code {
     /// hcaptcha = {version = "2.3.0", default-features = false, features = [rustls-backend]}
     /// ```
     SecretVersionUnknown,
+    /// The hostname of the response is not allowed by the [VerificationPolicy](crate::VerificationPolicy).
+    HostnameMismatch,
+    /// The challenge was solved longer ago than the [VerificationPolicy](crate::VerificationPolicy) allows.
+    ChallengeTooOld,
+    /// The captcha was not solved for the sitekey of the [VerificationPolicy](crate::VerificationPolicy).
+    SitekeyMismatch,
+    /// No secret is registered for the sitekey of the captcha in the [SecretRegistry](crate::SecretRegistry).
+    UnknownSitekey,
     /// Collect any new error codes issued by the API.
     Unknown(String),
 }
@@ -131,6 +198,19 @@ impl fmt::Display for Code {
             Code::SecretVersionUnknown => {
                 write!(f, "The version of the site secret is not recognise.")
             }
+            Code::HostnameMismatch => {
+                write!(
+                    f,
+                    "The captcha was solved on a hostname that is not allowed."
+                )
+            }
+            Code::ChallengeTooOld => write!(f, "The captcha was solved too long ago."),
+            Code::SitekeyMismatch => {
+                write!(f, "The captcha was not solved for the expected sitekey.")
+            }
+            Code::UnknownSitekey => {
+                write!(f, "No secret is registered for the sitekey.")
+            }
             Code::Unknown(e) => write!(f, "Unkown error: {e}"),
         }
     }
@@ -0,0 +1,322 @@
+//! Helpers shared by the integrations with HTTP server frameworks.
+
+#[cfg(any(feature = "axum", feature = "tower", feature = "actix"))]
+use crate::domain::HcaptchaSecret;
+use crate::{Code, HcaptchaError};
+#[cfg(any(feature = "axum", feature = "tower", feature = "actix"))]
+use crate::{HcaptchaCaptcha, HcaptchaClient, HcaptchaRequest, HcaptchaResponse};
+#[cfg(any(feature = "tower", feature = "actix"))]
+use http::header::HeaderName;
+use http::StatusCode;
+#[cfg(any(feature = "axum", feature = "tower", feature = "actix"))]
+use serde_json::Value;
+#[cfg(any(feature = "axum", feature = "tower", feature = "actix"))]
+use std::collections::HashSet;
+
+/// Format of a request body carrying the token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BodyFormat {
+    /// `application/x-www-form-urlencoded`
+    Form,
+    /// `application/json` or a `+json` media type.
+    Json,
+}
+
+/// Location of the token in the request.
+#[cfg(any(feature = "tower", feature = "actix"))]
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tower", feature = "actix"))))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenSource {
+    /// The value of the header.
+    Header(HeaderName),
+    /// The value of the field in the query string.
+    Query(String),
+    /// The value of the field in a form or JSON body.
+    ///
+    /// The body is buffered to read the field, and rebuilt from the
+    /// buffered bytes before the request is forwarded.
+    Body(String),
+}
+
+#[cfg(any(feature = "tower", feature = "actix"))]
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl Default for TokenSource {
+    /// The `h-captcha-response` field of the body, as submitted by the
+    /// hcaptcha widget in a form.
+    fn default() -> TokenSource {
+        TokenSource::Body("h-captcha-response".to_owned())
+    }
+}
+
+#[cfg(any(feature = "tower", feature = "actix"))]
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl TokenSource {
+    /// Read the token from the header.
+    ///
+    /// # Panic
+    ///
+    /// The function panics if the name is not a valid header name.
+    pub fn header(name: &str) -> TokenSource {
+        TokenSource::Header(HeaderName::from_bytes(name.as_bytes()).expect("invalid header name"))
+    }
+
+    /// Read the token from the field of the query string.
+    pub fn query(field: &str) -> TokenSource {
+        TokenSource::Query(field.to_owned())
+    }
+
+    /// Read the token from the field of a form or JSON body.
+    pub fn body(field: &str) -> TokenSource {
+        TokenSource::Body(field.to_owned())
+    }
+}
+
+/// Determine the format of the body from its content type.
+pub(crate) fn body_format(content_type: &str) -> Option<BodyFormat> {
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+    if mime == "application/x-www-form-urlencoded" {
+        Some(BodyFormat::Form)
+    } else if mime == "application/json" || mime.ends_with("+json") {
+        Some(BodyFormat::Json)
+    } else {
+        None
+    }
+}
+
+/// Get the string value of a top level field of the body.
+///
+/// An error describing the problem is returned if the body cannot be
+/// parsed in the format.
+#[cfg(any(feature = "axum", feature = "tower", feature = "actix"))]
+pub(crate) fn body_field(
+    format: BodyFormat,
+    body: &[u8],
+    field: &str,
+) -> Result<Option<String>, String> {
+    match format {
+        BodyFormat::Form => {
+            let pairs: Vec<(String, String)> =
+                serde_urlencoded::from_bytes(body).map_err(|e| e.to_string())?;
+            Ok(pairs.into_iter().find(|(k, _)| k == field).map(|(_, v)| v))
+        }
+        BodyFormat::Json => {
+            let value: Value = serde_json::from_slice(body).map_err(|e| e.to_string())?;
+            Ok(value
+                .get(field)
+                .and_then(Value::as_str)
+                .map(ToOwned::to_owned))
+        }
+    }
+}
+
+/// Get the value of a field of the query string.
+#[cfg(any(feature = "tower", feature = "actix"))]
+pub(crate) fn query_field(query: Option<&str>, field: &str) -> Option<String> {
+    let pairs: Vec<(String, String)> = serde_urlencoded::from_str(query?).ok()?;
+    pairs.into_iter().find(|(k, _)| k == field).map(|(_, v)| v)
+}
+
+/// Status code reported to the client when the verification fails.
+///
+/// Error codes are reported with the most severe status of the codes, see
+/// [code_status]. The API being unavailable, the rate limit of the client
+/// being exceeded or its circuit breaker being open is
+/// `503 Service Unavailable`, and anything else
+/// `500 Internal Server Error`.
+pub(crate) fn error_status(error: &HcaptchaError) -> StatusCode {
+    match error {
+        HcaptchaError::Codes(codes) => {
+            let statuses: Vec<StatusCode> = codes.iter().map(code_status).collect();
+            [StatusCode::INTERNAL_SERVER_ERROR, StatusCode::BAD_REQUEST]
+                .iter()
+                .copied()
+                .find(|status| statuses.contains(status))
+                .unwrap_or(StatusCode::FORBIDDEN)
+        }
+        #[cfg(feature = "reqwest")]
+        HcaptchaError::Reqwest(_) => StatusCode::SERVICE_UNAVAILABLE,
+        HcaptchaError::HttpStatus { .. }
+        | HcaptchaError::Decode { .. }
+        | HcaptchaError::Timeout(_)
+        | HcaptchaError::Transport(_)
+        | HcaptchaError::RateLimited
+        | HcaptchaError::CircuitOpen => StatusCode::SERVICE_UNAVAILABLE,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
+/// Status code for an error code.
+///
+/// * `500 Internal Server Error` - the secret is missing, invalid or does
+///   not match the sitekey, which the client cannot correct
+/// * `400 Bad Request` - the token, remoteip or sitekey submitted by the
+///   client is missing or malformed
+/// * `403 Forbidden` - the token was rejected, has been used before, or the
+///   response does not comply with the verification policy
+pub(crate) fn code_status(code: &Code) -> StatusCode {
+    match code {
+        Code::MissingSecret
+        | Code::InvalidSecret
+        | Code::SiteSecretMismatch
+        | Code::InvalidSecretExtWrongLen
+        | Code::InvalidSecretExtNotHex
+        | Code::SecretVersionUnknown => StatusCode::INTERNAL_SERVER_ERROR,
+        Code::MissingUserIp
+        | Code::InvalidUserIp
+        | Code::MissingSiteKey
+        | Code::InvalidSiteKey
+        | Code::MissingResponse
+        | Code::BadRequest
+        | Code::UnknownSitekey => StatusCode::BAD_REQUEST,
+        Code::InvalidResponse
+        | Code::InvalidAlreadySeen
+        | Code::HostnameMismatch
+        | Code::ChallengeTooOld
+        | Code::SitekeyMismatch
+        | Code::Unknown(_) => StatusCode::FORBIDDEN,
+    }
+}
+
+/// Secret given to the config of an integration.
+///
+/// The secret is checked when the config is created. An invalid secret is
+/// reported with its codes by each verification, as it would be if checked
+/// when the request is built.
+#[cfg(any(feature = "axum", feature = "tower", feature = "actix"))]
+#[derive(Debug, Clone)]
+pub(crate) struct ConfigSecret(Result<HcaptchaSecret, HashSet<Code>>);
+
+#[cfg(any(feature = "axum", feature = "tower", feature = "actix"))]
+impl ConfigSecret {
+    /// Check the secret.
+    pub(crate) fn new(secret: &str) -> ConfigSecret {
+        ConfigSecret(
+            HcaptchaSecret::parse(secret.to_owned()).map_err(|e| match e {
+                HcaptchaError::Codes(codes) => codes,
+                _ => [Code::InvalidSecret].iter().cloned().collect(),
+            }),
+        )
+    }
+
+    /// Get the secret, or the error for an invalid secret.
+    pub(crate) fn get(&self) -> Result<&HcaptchaSecret, HcaptchaError> {
+        self.0
+            .as_ref()
+            .map_err(|codes| HcaptchaError::Codes(codes.clone()))
+    }
+}
+
+/// Verify the token, submitted from the remoteip, with the client.
+#[cfg(any(feature = "axum", feature = "tower", feature = "actix"))]
+pub(crate) async fn verify_token(
+    client: &HcaptchaClient,
+    secret: &ConfigSecret,
+    token: &str,
+    remoteip: Option<&str>,
+) -> Result<HcaptchaResponse, HcaptchaError> {
+    let mut captcha = HcaptchaCaptcha::new(token)?;
+    if let Some(remoteip) = remoteip {
+        captcha = captcha.set_remoteip(remoteip)?;
+    }
+    let request = HcaptchaRequest::with_secret(secret.get()?.clone(), captcha);
+    client.verify_client_response(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn body_format_from_content_type() {
+        assert_eq!(
+            body_format("application/x-www-form-urlencoded"),
+            Some(BodyFormat::Form)
+        );
+        assert_eq!(
+            body_format("Application/JSON; charset=utf-8"),
+            Some(BodyFormat::Json)
+        );
+        assert_eq!(
+            body_format("application/vnd.api+json"),
+            Some(BodyFormat::Json)
+        );
+        assert_eq!(body_format("text/plain"), None);
+    }
+
+    #[cfg(any(feature = "axum", feature = "tower", feature = "actix"))]
+    #[test]
+    fn config_secret_reports_invalid_secret() {
+        let secret = "0x123456789abcde0f123456789abcdef012345678";
+        assert_eq!(ConfigSecret::new(secret).get().unwrap().expose(), secret);
+        match ConfigSecret::new(" ").get() {
+            Err(HcaptchaError::Codes(codes)) => assert!(codes.contains(&Code::MissingSecret)),
+            r => panic!("expected error codes, got {:?}", r),
+        }
+    }
+
+    fn codes(codes: &[Code]) -> HcaptchaError {
+        HcaptchaError::Codes(codes.iter().cloned().collect())
+    }
+
+    #[test]
+    fn error_status_is_most_severe_code_status() {
+        assert_eq!(
+            error_status(&codes(&[Code::InvalidResponse])),
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            error_status(&codes(&[Code::InvalidResponse, Code::MissingUserIp])),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            error_status(&codes(&[Code::MissingResponse, Code::InvalidSecret])),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(error_status(&codes(&[])), StatusCode::FORBIDDEN);
+        assert_eq!(
+            error_status(&HcaptchaError::Timeout(std::time::Duration::from_secs(1))),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            error_status(&HcaptchaError::RateLimited),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        assert_eq!(
+            error_status(&HcaptchaError::CircuitOpen),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+    }
+
+    #[cfg(any(feature = "axum", feature = "tower", feature = "actix"))]
+    #[test]
+    fn fields_are_found() {
+        assert_eq!(
+            body_field(BodyFormat::Form, b"a=1&token=abc", "token"),
+            Ok(Some("abc".to_owned()))
+        );
+        assert_eq!(
+            body_field(BodyFormat::Json, br#"{"token":"abc","a":1}"#, "token"),
+            Ok(Some("abc".to_owned()))
+        );
+        assert_eq!(
+            body_field(BodyFormat::Json, br#"{"token":1}"#, "token"),
+            Ok(None)
+        );
+        assert!(body_field(BodyFormat::Json, b"not json", "token").is_err());
+    }
+
+    #[cfg(any(feature = "tower", feature = "actix"))]
+    #[test]
+    fn query_field_is_found() {
+        assert_eq!(
+            query_field(Some("a=1&token=abc"), "token"),
+            Some("abc".to_owned())
+        );
+        assert_eq!(query_field(None, "token"), None);
+    }
+}
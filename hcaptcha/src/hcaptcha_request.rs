@@ -326,6 +326,11 @@ impl HcaptchaRequest {
         Ok(self)
     }
 
+    /// Create a request from a secret that has already been validated.
+    pub(crate) fn with_secret(secret: HcaptchaSecret, captcha: HcaptchaCaptcha) -> HcaptchaRequest {
+        HcaptchaRequest { captcha, secret }
+    }
+
     #[allow(dead_code)]
     pub(crate) fn secret(&self) -> HcaptchaSecret {
         self.secret.clone()
@@ -389,7 +394,7 @@ mod tests {
 
         let request = HcaptchaRequest::new_from_response(&secret, &response).unwrap();
 
-        assert_eq!(&secret, &request.secret().to_string().as_str());
+        assert_eq!(&secret, &request.secret().expose());
 
         let HcaptchaCaptcha {
             response: resp,
@@ -54,19 +54,41 @@
 //! #       .set_sitekey(&mockd::unique::uuid_v4())
 //! #       .unwrap()
 //! #       }
-
 //! ```
 use crate::Code;
 use crate::HcaptchaError;
+#[cfg(feature = "chrono")]
+use chrono::{DateTime, TimeDelta, Utc};
 use serde::Deserialize;
-use std::collections::HashSet;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 
+#[cfg(feature = "enterprise")]
+mod enterprise;
+
+#[cfg(feature = "enterprise")]
+use enterprise::{is, lenient, FieldCheck};
+#[cfg(feature = "enterprise")]
+pub use enterprise::{Geolocation, UserAgent};
+
 type Score = f32;
 
+/// Enterprise fields of the [HcaptchaResponse] kept in
+/// [extra](HcaptchaResponse::extra) when of an unexpected type.
+#[cfg(feature = "enterprise")]
+const ENTERPRISE_FIELDS: &[FieldCheck] = &[
+    ("behavior_counts", is::<HashMap<String, u64>>),
+    ("risk_insights", is::<HashMap<String, Value>>),
+    ("sig", is::<String>),
+    ("geolocation", is::<Geolocation>),
+    ("user_agent", is::<UserAgent>),
+];
+
 /// Result from call to verify the client's response
 #[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
 #[derive(Debug, Default, Deserialize, Clone)]
+#[cfg_attr(feature = "enterprise", serde(remote = "Self"))]
 pub struct HcaptchaResponse {
     /// verification status: true or false.
     ///
@@ -77,6 +99,10 @@ pub struct HcaptchaResponse {
     challenge_ts: Option<String>, //yyyy-MM-dd'T'HH:mm:ssZZ
     /// the hostname of the site where the captcha was solved
     hostname: Option<String>,
+    /// `enterprise` feature: the sitekey the captcha was solved for.
+    #[cfg(feature = "enterprise")]
+    #[serde(default)]
+    sitekey: Option<String>,
     /// optional: whether the response will be credited
     credit: Option<bool>,
     /// optional: any error codes
@@ -92,6 +118,46 @@ pub struct HcaptchaResponse {
     #[allow(dead_code)]
     #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
     score_reason: Option<HashSet<String>>,
+    /// `enterprise` feature: counts of behaviour observed for the client.
+    #[cfg(feature = "enterprise")]
+    #[serde(default)]
+    behavior_counts: Option<HashMap<String, u64>>,
+    /// `enterprise` feature: risk insights for the client.
+    #[cfg(feature = "enterprise")]
+    #[serde(default)]
+    risk_insights: Option<HashMap<String, Value>>,
+    /// `enterprise` feature: signature of the response.
+    #[cfg(feature = "enterprise")]
+    #[serde(default)]
+    sig: Option<String>,
+    /// `enterprise` feature: geolocation of the client IP address.
+    #[cfg(feature = "enterprise")]
+    #[serde(default)]
+    geolocation: Option<Geolocation>,
+    /// `enterprise` feature: details of the client user agent.
+    #[cfg(feature = "enterprise")]
+    #[serde(default)]
+    user_agent: Option<UserAgent>,
+    /// Set on a response created by the client while its circuit breaker
+    /// is open, in place of a response from the API.
+    #[serde(skip)]
+    fail_open: bool,
+    /// Fields returned by the API that are not modelled.
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[cfg(feature = "enterprise")]
+impl<'de> Deserialize<'de> for HcaptchaResponse {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let (mut response, malformed) =
+            lenient(de, ENTERPRISE_FIELDS, HcaptchaResponse::deserialize)?;
+        response.extra.extend(malformed);
+        Ok(response)
+    }
 }
 
 #[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
@@ -175,6 +241,15 @@ impl fmt::Display for HcaptchaResponse {
 
 #[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
 impl HcaptchaResponse {
+    /// Create a successful response flagged as [fail open](HcaptchaResponse::is_fail_open).
+    pub(crate) fn fail_open() -> HcaptchaResponse {
+        HcaptchaResponse {
+            success: true,
+            fail_open: true,
+            ..HcaptchaResponse::default()
+        }
+    }
+
     /// Check success of API call and return HcaptchaError
     /// with the error codes if not successful.
     pub(crate) fn check_error(&self) -> Result<(), HcaptchaError> {
@@ -228,7 +303,7 @@ impl HcaptchaResponse {
     /// #       .set_sitekey(&mockd::unique::uuid_v4())
     /// #       .unwrap()
     /// #       }
-
+    /// ```
     #[allow(dead_code)]
     pub fn success(&self) -> bool {
         self.success
@@ -273,12 +348,37 @@ impl HcaptchaResponse {
     /// #       .set_sitekey(&mockd::unique::uuid_v4())
     /// #       .unwrap()
     /// #       }
-
+    /// ```
     #[allow(dead_code)]
     pub fn hostname(&self) -> Option<String> {
         self.hostname.clone()
     }
 
+    /// Get the value of the sitekey field
+    ///
+    /// The sitekey is only returned by the API for enterprise accounts.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), serde_json::Error> {
+    ///     use hcaptcha::HcaptchaResponse;
+    ///
+    ///     let response: HcaptchaResponse = serde_json::from_str(
+    ///         r#"{"success":true,"sitekey":"10000000-ffff-ffff-ffff-000000000001"}"#,
+    ///     )?;
+    ///
+    ///     if let Some(sitekey) = response.sitekey() {
+    ///         println!("Sitekey: {}", sitekey);
+    ///     };
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "enterprise")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+    pub fn sitekey(&self) -> Option<String> {
+        self.sitekey.clone()
+    }
+
     /// Get the value of the timestamp field
     ///
     /// # Example
@@ -318,12 +418,70 @@ impl HcaptchaResponse {
     /// #       .set_sitekey(&mockd::unique::uuid_v4())
     /// #       .unwrap()
     /// #       }
-
+    /// ```
     #[allow(dead_code)]
     pub fn timestamp(&self) -> Option<String> {
         self.challenge_ts.clone()
     }
 
+    /// Get the timestamp of the captcha parsed as a UTC date and time
+    ///
+    /// Returns `None` if the timestamp is missing or is not a valid RFC 3339
+    /// date and time.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), serde_json::Error> {
+    ///     use hcaptcha::HcaptchaResponse;
+    ///
+    ///     let response: HcaptchaResponse = serde_json::from_str(
+    ///         r#"{"success":true,"challenge_ts":"2020-11-11T23:27:00Z"}"#,
+    ///     )?;
+    ///
+    ///     if let Some(timestamp) = response.timestamp_utc() {
+    ///         println!("Timestamp: {}", timestamp.to_rfc2822());
+    ///     };
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    pub fn timestamp_utc(&self) -> Option<DateTime<Utc>> {
+        let timestamp = self.challenge_ts.as_deref()?;
+        DateTime::parse_from_rfc3339(timestamp)
+            .ok()
+            .map(|t| t.with_timezone(&Utc))
+    }
+
+    /// Get the time elapsed between solving the captcha and `now`
+    ///
+    /// Use the age to reject tokens solved too long ago. Returns `None` if
+    /// the timestamp is missing or cannot be parsed. The age is negative if
+    /// the timestamp is after `now`.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), serde_json::Error> {
+    ///     use chrono::{TimeDelta, Utc};
+    ///     use hcaptcha::HcaptchaResponse;
+    ///
+    ///     let response: HcaptchaResponse = serde_json::from_str(
+    ///         r#"{"success":true,"challenge_ts":"2020-11-11T23:27:00Z"}"#,
+    ///     )?;
+    ///
+    ///     let too_old = response
+    ///         .age(Utc::now())
+    ///         .map_or(true, |age| age > TimeDelta::try_minutes(2).unwrap());
+    ///     assert!(too_old);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "chrono")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "chrono")))]
+    pub fn age(&self, now: DateTime<Utc>) -> Option<TimeDelta> {
+        self.timestamp_utc().map(|timestamp| now - timestamp)
+    }
+
     /// Get the value of the credit field
     ///
     /// # Example
@@ -364,7 +522,7 @@ impl HcaptchaResponse {
     /// #       .set_sitekey(&mockd::unique::uuid_v4())
     /// #       .unwrap()
     /// # }
-
+    /// ```
     #[allow(dead_code)]
     pub fn credit(&self) -> Option<bool> {
         self.credit
@@ -410,6 +568,7 @@ impl HcaptchaResponse {
     /// #       .set_sitekey(&mockd::unique::uuid_v4())
     /// #       .unwrap()
     /// #       }
+    /// ```
     #[allow(dead_code)]
     pub fn error_codes(&self) -> Option<HashSet<Code>> {
         self.error_codes.clone()
@@ -455,7 +614,7 @@ impl HcaptchaResponse {
     /// #       .set_sitekey(&mockd::unique::uuid_v4())
     /// #       .unwrap()
     /// #       }
-
+    /// ```
     #[cfg(feature = "enterprise")]
     #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
     #[allow(dead_code)]
@@ -503,13 +662,86 @@ impl HcaptchaResponse {
     /// #       .set_sitekey(&mockd::unique::uuid_v4())
     /// #       .unwrap()
     /// #       }
-
+    /// ```
     #[allow(dead_code)]
     #[cfg(feature = "enterprise")]
     #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
     pub fn score_reason(&self) -> Option<HashSet<String>> {
         self.score_reason.clone()
     }
+
+    /// Get the value of the behavior_counts field
+    ///
+    /// A value that is not a map of counts is reported as `None` and kept in
+    /// [extra](HcaptchaResponse::extra), as is a value of an unexpected type
+    /// for any of the enterprise fields.
+    #[cfg(feature = "enterprise")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+    pub fn behavior_counts(&self) -> Option<HashMap<String, u64>> {
+        self.behavior_counts.clone()
+    }
+
+    /// Get the value of the risk_insights field
+    #[cfg(feature = "enterprise")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+    pub fn risk_insights(&self) -> Option<HashMap<String, Value>> {
+        self.risk_insights.clone()
+    }
+
+    /// Get the value of the sig field
+    #[cfg(feature = "enterprise")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+    pub fn sig(&self) -> Option<String> {
+        self.sig.clone()
+    }
+
+    /// Get the value of the geolocation field
+    #[cfg(feature = "enterprise")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+    pub fn geolocation(&self) -> Option<Geolocation> {
+        self.geolocation.clone()
+    }
+
+    /// Get the value of the user_agent field
+    #[cfg(feature = "enterprise")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+    pub fn user_agent(&self) -> Option<UserAgent> {
+        self.user_agent.clone()
+    }
+
+    /// Get the fields returned by the API that are not modelled
+    ///
+    /// Without the `enterprise` feature the enterprise fields are kept here.
+    /// With it, an enterprise field with a value of an unexpected type is
+    /// kept here.
+    ///
+    /// # Example
+    /// ```
+    /// # fn main() -> Result<(), serde_json::Error> {
+    ///     use hcaptcha::HcaptchaResponse;
+    ///
+    ///     let response: HcaptchaResponse =
+    ///         serde_json::from_str(r#"{"success":true,"new_field":42}"#)?;
+    ///
+    ///     assert_eq!(response.extra()["new_field"], 42);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
+
+    /// Report if the response was created by the client in place of a
+    /// response from the API.
+    ///
+    /// A [CircuitBreaker](crate::CircuitBreaker) with the
+    /// [FailOpen](crate::OpenPolicy::FailOpen) policy accepts verifications
+    /// while the API is unreachable. Such a response is successful but the
+    /// token has not been verified, so the application may want to apply
+    /// further checks.
+    pub fn is_fail_open(&self) -> bool {
+        self.fail_open
+    }
 }
 
 #[cfg(test)]
@@ -567,6 +799,47 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn timestamp_utc_test() {
+        use chrono::TimeZone;
+
+        let response = test_response();
+
+        assert_eq!(
+            response.timestamp_utc(),
+            Some(
+                chrono::Utc
+                    .with_ymd_and_hms(2020, 11, 11, 23, 27, 0)
+                    .unwrap()
+            )
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn age_test() {
+        use chrono::{TimeDelta, TimeZone, Utc};
+
+        let response = test_response();
+        let now = Utc.with_ymd_and_hms(2020, 11, 11, 23, 29, 30).unwrap();
+
+        assert_eq!(response.age(now), TimeDelta::try_seconds(150));
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn invalid_timestamp_has_no_age() {
+        let response: HcaptchaResponse = serde_json::from_value(json!({
+            "success": true,
+            "challenge_ts": "yesterday",
+        }))
+        .unwrap();
+
+        assert!(response.timestamp_utc().is_none());
+        assert!(response.age(chrono::Utc::now()).is_none());
+    }
+
     #[test]
     fn hostname_test() {
         let response = test_response();
@@ -591,6 +864,91 @@ mod tests {
         }
     }
 
+    #[test]
+    fn unknown_fields_are_kept() {
+        let response: HcaptchaResponse = serde_json::from_value(json!({
+            "success": true,
+            "new_field": {"nested": true},
+        }))
+        .unwrap();
+
+        assert_eq!(response.extra()["new_field"], json!({"nested": true}));
+    }
+
+    #[cfg(not(feature = "enterprise"))]
+    #[test]
+    fn enterprise_fields_are_kept_as_extra() {
+        let response: HcaptchaResponse = serde_json::from_value(json!({
+            "success": true,
+            "sig": "signature",
+        }))
+        .unwrap();
+
+        assert_eq!(response.extra()["sig"], json!("signature"));
+    }
+
+    #[cfg(feature = "enterprise")]
+    #[test]
+    fn enterprise_fields_test() {
+        let response: HcaptchaResponse = serde_json::from_value(json!({
+            "success": true,
+            "score": 0.2,
+            "sig": "signature",
+            "behavior_counts": {"sessions": 3, "solves": 1},
+            "risk_insights": {"vpn": true},
+            "geolocation": {"country": "IE"},
+            "user_agent": {"browser": "Firefox", "device_type": "desktop"},
+            "new_field": 1,
+        }))
+        .unwrap();
+
+        assert_eq!(response.sig(), Some("signature".to_owned()));
+        assert_eq!(response.behavior_counts().unwrap()["solves"], 1);
+        assert_eq!(response.risk_insights().unwrap()["vpn"], json!(true));
+        assert_eq!(
+            response.geolocation().unwrap().country(),
+            Some("IE".to_owned())
+        );
+        assert_eq!(
+            response.user_agent().unwrap().device_type(),
+            Some("desktop".to_owned())
+        );
+        assert_eq!(response.extra().len(), 1);
+        assert_eq!(response.extra()["new_field"], json!(1));
+    }
+
+    #[cfg(feature = "enterprise")]
+    #[test]
+    fn malformed_enterprise_field_does_not_fail() {
+        let response: HcaptchaResponse = serde_json::from_value(json!({
+            "success": true,
+            "behavior_counts": "unavailable",
+        }))
+        .unwrap();
+
+        assert!(response.success());
+        assert!(response.behavior_counts().is_none());
+        assert_eq!(response.extra()["behavior_counts"], json!("unavailable"));
+    }
+
+    #[cfg(feature = "enterprise")]
+    #[test]
+    fn malformed_nested_enterprise_field_is_kept() {
+        let response: HcaptchaResponse = serde_json::from_value(json!({
+            "success": true,
+            "geolocation": {"country": "IE", "asn": "AS1234"},
+            "user_agent": ["Firefox"],
+        }))
+        .unwrap();
+
+        let geolocation = response.geolocation().unwrap();
+        assert_eq!(geolocation.country(), Some("IE".to_owned()));
+        assert_eq!(geolocation.asn(), None);
+        assert_eq!(geolocation.extra()["asn"], json!("AS1234"));
+        assert!(response.user_agent().is_none());
+        assert_eq!(response.extra()["user_agent"], json!(["Firefox"]));
+    }
+
     #[cfg(feature = "enterprise")]
     #[test]
     fn score_test() {
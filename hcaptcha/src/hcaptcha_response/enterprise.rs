@@ -0,0 +1,243 @@
+//! Typed fields returned by the enterprise siteverify API.
+
+use serde::de::{DeserializeOwned, Error};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+/// Name of a field and a check that its value has the expected type.
+pub(crate) type FieldCheck = (&'static str, fn(&Value) -> bool);
+
+/// Typed fields of the [Geolocation].
+const GEOLOCATION_FIELDS: &[FieldCheck] = &[
+    ("country", is::<String>),
+    ("region", is::<String>),
+    ("city", is::<String>),
+    ("asn", is::<u64>),
+];
+
+/// Typed fields of the [UserAgent].
+const USER_AGENT_FIELDS: &[FieldCheck] = &[
+    ("browser", is::<String>),
+    ("browser_version", is::<String>),
+    ("os", is::<String>),
+    ("os_version", is::<String>),
+    ("device_type", is::<String>),
+];
+
+/// Geolocation of the client IP address reported by the enterprise API.
+///
+/// Fields not modelled here, and modelled fields with a value of an
+/// unexpected type, are kept in [extra](Geolocation::extra).
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+#[derive(Debug, Default, Deserialize, Clone, PartialEq)]
+#[serde(remote = "Self")]
+pub struct Geolocation {
+    /// ISO 3166 country code.
+    #[serde(default)]
+    country: Option<String>,
+    /// Region or subdivision of the country.
+    #[serde(default)]
+    region: Option<String>,
+    /// City.
+    #[serde(default)]
+    city: Option<String>,
+    /// Autonomous system number of the network.
+    #[serde(default)]
+    asn: Option<u64>,
+    /// Any other fields returned.
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl Geolocation {
+    /// Get the ISO 3166 country code.
+    pub fn country(&self) -> Option<String> {
+        self.country.clone()
+    }
+
+    /// Get the region or subdivision of the country.
+    pub fn region(&self) -> Option<String> {
+        self.region.clone()
+    }
+
+    /// Get the city.
+    pub fn city(&self) -> Option<String> {
+        self.city.clone()
+    }
+
+    /// Get the autonomous system number of the network.
+    pub fn asn(&self) -> Option<u64> {
+        self.asn
+    }
+
+    /// Get the fields that are not modelled or have a value of an
+    /// unexpected type.
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
+}
+
+/// Details of the client user agent reported by the enterprise API.
+///
+/// Fields not modelled here, and modelled fields with a value of an
+/// unexpected type, are kept in [extra](UserAgent::extra).
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "enterprise")))]
+#[derive(Debug, Default, Deserialize, Clone, PartialEq)]
+#[serde(remote = "Self")]
+pub struct UserAgent {
+    /// Browser name.
+    #[serde(default)]
+    browser: Option<String>,
+    /// Browser version.
+    #[serde(default)]
+    browser_version: Option<String>,
+    /// Operating system name.
+    #[serde(default)]
+    os: Option<String>,
+    /// Operating system version.
+    #[serde(default)]
+    os_version: Option<String>,
+    /// Type of device, such as desktop or mobile.
+    #[serde(default)]
+    device_type: Option<String>,
+    /// Any other fields returned.
+    #[serde(flatten)]
+    extra: HashMap<String, Value>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl UserAgent {
+    /// Get the browser name.
+    pub fn browser(&self) -> Option<String> {
+        self.browser.clone()
+    }
+
+    /// Get the browser version.
+    pub fn browser_version(&self) -> Option<String> {
+        self.browser_version.clone()
+    }
+
+    /// Get the operating system name.
+    pub fn os(&self) -> Option<String> {
+        self.os.clone()
+    }
+
+    /// Get the operating system version.
+    pub fn os_version(&self) -> Option<String> {
+        self.os_version.clone()
+    }
+
+    /// Get the type of device.
+    pub fn device_type(&self) -> Option<String> {
+        self.device_type.clone()
+    }
+
+    /// Get the fields that are not modelled or have a value of an
+    /// unexpected type.
+    pub fn extra(&self) -> &HashMap<String, Value> {
+        &self.extra
+    }
+}
+
+impl<'de> Deserialize<'de> for Geolocation {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (mut geolocation, malformed) =
+            lenient(de, GEOLOCATION_FIELDS, Geolocation::deserialize)?;
+        geolocation.extra.extend(malformed);
+        Ok(geolocation)
+    }
+}
+
+impl<'de> Deserialize<'de> for UserAgent {
+    fn deserialize<D>(de: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let (mut user_agent, malformed) = lenient(de, USER_AGENT_FIELDS, UserAgent::deserialize)?;
+        user_agent.extra.extend(malformed);
+        Ok(user_agent)
+    }
+}
+
+/// Report if the value deserializes to the type, or is null.
+pub(crate) fn is<T: DeserializeOwned>(value: &Value) -> bool {
+    Option::<T>::deserialize(value).is_ok()
+}
+
+/// Deserialize an object with the derived deserializer, taking out the
+/// fields with a value of an unexpected type.
+///
+/// The typed fields read as absent and their values are returned, to be kept
+/// with the fields that are not modelled, so a change to the API neither
+/// fails the verification nor loses the value.
+pub(crate) fn lenient<'de, D, T, F>(
+    de: D,
+    fields: &[FieldCheck],
+    derived: F,
+) -> Result<(T, HashMap<String, Value>), D::Error>
+where
+    D: Deserializer<'de>,
+    F: FnOnce(Value) -> Result<T, serde_json::Error>,
+{
+    let mut object = Map::<String, Value>::deserialize(de)?;
+    let names = fields
+        .iter()
+        .filter(|(name, check)| object.get(*name).is_some_and(|v| !check(v)))
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>();
+    let malformed = names
+        .into_iter()
+        .filter_map(|name| object.remove_entry(name))
+        .collect();
+    let typed = derived(Value::Object(object)).map_err(D::Error::custom)?;
+    Ok((typed, malformed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn geolocation_keeps_unknown_fields() {
+        let geolocation: Geolocation = serde_json::from_value(json!({
+            "country": "IE",
+            "city": "Dublin",
+            "asn": 1234,
+            "timezone": "Europe/Dublin",
+        }))
+        .unwrap();
+        assert_eq!(geolocation.country(), Some("IE".to_owned()));
+        assert_eq!(geolocation.region(), None);
+        assert_eq!(geolocation.asn(), Some(1234));
+        assert_eq!(
+            geolocation.extra().get("timezone"),
+            Some(&json!("Europe/Dublin"))
+        );
+    }
+
+    #[test]
+    fn unexpected_type_is_kept_in_extra() {
+        let user_agent: UserAgent = serde_json::from_value(json!({
+            "browser": "Firefox",
+            "os": { "name": "Linux" },
+            "device_type": null,
+        }))
+        .unwrap();
+        assert_eq!(user_agent.browser(), Some("Firefox".to_owned()));
+        assert_eq!(user_agent.os(), None);
+        assert_eq!(user_agent.device_type(), None);
+        assert_eq!(
+            user_agent.extra().get("os"),
+            Some(&json!({ "name": "Linux" }))
+        );
+        assert!(!user_agent.extra().contains_key("device_type"));
+    }
+}
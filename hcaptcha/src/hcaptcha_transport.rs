@@ -0,0 +1,141 @@
+//! Transport used by the [HcaptchaClient](crate::HcaptchaClient) to call the
+//! Hcaptcha API.
+//!
+//! The transport posts the form encoded request to the API url and returns
+//! the status and body of the response. The [HcaptchaClient](crate::HcaptchaClient)
+//! checks the status and decodes the body.
+//!
+//! A transport based on reqwest, [ReqwestTransport], is provided with the
+//! `reqwest` feature, which is enabled by default and by the `rustls-backend`
+//! and `nativetls-backend` features. Any other HTTP client can be used by
+//! implementing [HcaptchaTransport].
+//!
+//! # Example
+//! Implement a transport for another HTTP client.
+//! ```
+//!     use hcaptcha::{async_trait, HcaptchaClient, HcaptchaError};
+//!     use hcaptcha::{HcaptchaTransport, TransportResponse};
+//!     use url::Url;
+//!
+//!     #[derive(Debug)]
+//!     struct MyTransport;
+//!
+//!     #[async_trait]
+//!     impl HcaptchaTransport for MyTransport {
+//!         async fn post_form(
+//!             &self,
+//!             url: &Url,
+//!             form: &str,
+//!         ) -> Result<TransportResponse, HcaptchaError> {
+//!             // Post the form with your HTTP client and return the response
+//!             Ok(TransportResponse::new(200, r#"{"success":true}"#)
+//!                 .content_type("application/json"))
+//!         }
+//!     }
+//!
+//!     let client = HcaptchaClient::with_transport(MyTransport);
+//! ```
+
+use crate::HcaptchaError;
+use async_trait::async_trait;
+use std::fmt;
+use url::Url;
+
+mod in_memory_transport;
+#[cfg(feature = "reqwest")]
+mod reqwest_transport;
+
+pub use in_memory_transport::InMemoryTransport;
+#[cfg(feature = "reqwest")]
+pub use reqwest_transport::ReqwestTransport;
+
+/// Send the form encoded request to the Hcaptcha API.
+#[async_trait]
+pub trait HcaptchaTransport: fmt::Debug + Send + Sync {
+    /// Post the form encoded body to the url and return the response.
+    ///
+    /// The body is sent with the content type
+    /// `application/x-www-form-urlencoded`.
+    ///
+    /// The body contains the secret and token in clear. It is borrowed from
+    /// the client, which zeroes the encoded form once the request is done.
+    /// A transport that copies the body should zero its copy, for example
+    /// with [zeroize::Zeroizing](https://docs.rs/zeroize), where the HTTP
+    /// client allows. The copy taken by [ReqwestTransport] is not zeroed.
+    ///
+    /// # Errors
+    ///
+    /// Return an error if no response is received, for example because the
+    /// connection failed. A response with any HTTP status should be returned
+    /// as a [TransportResponse].
+    ///
+    /// Report a failure to connect as an [HcaptchaError::Io] error of kind
+    /// [ConnectionRefused](std::io::ErrorKind::ConnectionRefused), so the
+    /// client knows the API has not seen the token. Other errors are assumed
+    /// to have reached the API.
+    async fn post_form(&self, url: &Url, form: &str) -> Result<TransportResponse, HcaptchaError>;
+}
+
+/// HTTP response returned by a [HcaptchaTransport].
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TransportResponse {
+    /// HTTP status code.
+    status: u16,
+    /// Value of the `Content-Type` header.
+    content_type: Option<String>,
+    /// Value of the `Retry-After` header.
+    retry_after: Option<String>,
+    /// Body of the response.
+    body: Vec<u8>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl TransportResponse {
+    /// Create a response with the HTTP status and body.
+    pub fn new(status: u16, body: impl Into<Vec<u8>>) -> TransportResponse {
+        TransportResponse {
+            status,
+            content_type: None,
+            retry_after: None,
+            body: body.into(),
+        }
+    }
+
+    /// Set the value of the `Content-Type` header.
+    pub fn content_type(mut self, content_type: &str) -> Self {
+        self.content_type = Some(content_type.to_owned());
+        self
+    }
+
+    /// Set the value of the `Retry-After` header.
+    pub fn retry_after(mut self, retry_after: &str) -> Self {
+        self.retry_after = Some(retry_after.to_owned());
+        self
+    }
+
+    /// Get the HTTP status code.
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    /// Get the value of the `Content-Type` header.
+    pub fn content_type_header(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Get the value of the `Retry-After` header.
+    pub fn retry_after_header(&self) -> Option<&str> {
+        self.retry_after.as_deref()
+    }
+
+    /// Get the body of the response.
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+
+    /// Report if the status is in the success (2xx) range.
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
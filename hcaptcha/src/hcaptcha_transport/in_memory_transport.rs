@@ -0,0 +1,119 @@
+//! In memory transport returning queued responses.
+//!
+//! # Example
+//! Verify a request without calling the Hcaptcha API.
+//! ```
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     use hcaptcha::{HcaptchaClient, HcaptchaRequest};
+//!     use hcaptcha::{InMemoryTransport, TransportResponse};
+//!
+//!     let transport = InMemoryTransport::new();
+//!     transport.push_response(TransportResponse::new(200, r#"{"success":true}"#));
+//!
+//!     let client = HcaptchaClient::with_transport(transport.clone());
+//! #   let secret = "0x123456789abcde0f123456789abcdef012345678";
+//! #   let token = "10000000-aaaa-bbbb-cccc-000000000001";
+//!     let request = HcaptchaRequest::new_from_response(secret, token)?;
+//!     let response = client.verify_client_response(request).await?;
+//!
+//!     assert!(response.success());
+//!     assert_eq!(transport.requests().len(), 1);
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{HcaptchaTransport, TransportResponse};
+use crate::HcaptchaError;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+/// Transport returning queued responses without making a network call.
+///
+/// Responses are returned in the order they were pushed. The url and form
+/// of each request are recorded. Clones share the queue and the record of
+/// requests, so a clone can be given to the client and the original used to
+/// inspect the requests.
+///
+/// If the queue is empty [HcaptchaError::Transport] is returned.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Default, Clone)]
+pub struct InMemoryTransport {
+    /// Responses waiting to be returned.
+    responses: Arc<Mutex<VecDeque<TransportResponse>>>,
+    /// Url and form of each request received.
+    requests: Arc<Mutex<Vec<(Url, String)>>>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl InMemoryTransport {
+    /// Create a new transport with an empty queue.
+    pub fn new() -> InMemoryTransport {
+        InMemoryTransport::default()
+    }
+
+    /// Add a response to the end of the queue.
+    pub fn push_response(&self, response: TransportResponse) {
+        self.responses
+            .lock()
+            .expect("response queue poisoned")
+            .push_back(response);
+    }
+
+    /// Get the url and form of each request received.
+    pub fn requests(&self) -> Vec<(Url, String)> {
+        self.requests
+            .lock()
+            .expect("request record poisoned")
+            .clone()
+    }
+}
+
+#[async_trait]
+impl HcaptchaTransport for InMemoryTransport {
+    async fn post_form(&self, url: &Url, form: &str) -> Result<TransportResponse, HcaptchaError> {
+        self.requests
+            .lock()
+            .expect("request record poisoned")
+            .push((url.clone(), form.to_owned()));
+        self.responses
+            .lock()
+            .expect("response queue poisoned")
+            .pop_front()
+            .ok_or_else(|| HcaptchaError::Transport("no response queued".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn responses_are_returned_in_order() {
+        let transport = InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(503, ""));
+        transport.push_response(TransportResponse::new(200, "{}"));
+        let url = Url::parse("https://domain.com/siteverify").unwrap();
+
+        let first = transport.post_form(&url, "a=1").await.unwrap();
+        let second = transport.post_form(&url, "a=2").await.unwrap();
+        assert_eq!(first.status(), 503);
+        assert_eq!(second.status(), 200);
+        assert_eq!(
+            transport.requests(),
+            vec![(url.clone(), "a=1".to_owned()), (url, "a=2".to_owned())]
+        );
+    }
+
+    #[tokio::test]
+    async fn empty_queue_is_an_error() {
+        let transport = InMemoryTransport::new();
+        let url = Url::parse("https://domain.com/siteverify").unwrap();
+        assert!(matches!(
+            transport.post_form(&url, "").await,
+            Err(HcaptchaError::Transport(_))
+        ));
+    }
+}
@@ -0,0 +1,118 @@
+//! Transport using a reqwest [Client].
+//!
+//! # Example
+//! Use an existing reqwest client.
+//! ```
+//!     use hcaptcha::{HcaptchaClient, ReqwestTransport};
+//!
+//!     let client = reqwest::Client::new();
+//!     let client = HcaptchaClient::with_transport(ReqwestTransport::from(client));
+//! ```
+
+use super::{HcaptchaTransport, TransportResponse};
+use crate::HcaptchaError;
+use async_trait::async_trait;
+use reqwest::header::{CONTENT_TYPE, RETRY_AFTER};
+use reqwest::Client;
+use std::time::Duration;
+use url::Url;
+
+/// Transport using a reqwest [Client].
+///
+/// This is the transport used by [HcaptchaClient::new](crate::HcaptchaClient::new)
+/// and the [HcaptchaClientBuilder](crate::HcaptchaClientBuilder).
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "reqwest")))]
+#[derive(Debug, Default, Clone)]
+pub struct ReqwestTransport {
+    /// The reqwest client.
+    client: Client,
+    /// Request timeout configured on the client, reported when a request
+    /// times out.
+    timeout: Option<Duration>,
+    /// Connect timeout configured on the client, reported when establishing
+    /// a connection times out.
+    connect_timeout: Option<Duration>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl ReqwestTransport {
+    /// Create a new transport with a default reqwest client.
+    pub fn new() -> ReqwestTransport {
+        ReqwestTransport::default()
+    }
+
+    /// Create a transport for a client configured with a request and a
+    /// connect timeout.
+    ///
+    /// The timeout that fired is reported in [HcaptchaError::Timeout].
+    pub(crate) fn with_timeouts(
+        client: Client,
+        timeout: Option<Duration>,
+        connect_timeout: Option<Duration>,
+    ) -> ReqwestTransport {
+        ReqwestTransport {
+            client,
+            timeout,
+            connect_timeout,
+        }
+    }
+
+    fn reqwest_error(&self, error: reqwest::Error) -> HcaptchaError {
+        if !error.is_timeout() {
+            return HcaptchaError::Reqwest(error);
+        }
+        // The request timeout also covers establishing the connection.
+        let timeout = if error.is_connect() {
+            self.connect_timeout.or(self.timeout)
+        } else {
+            self.timeout
+        };
+        match timeout {
+            Some(timeout) => HcaptchaError::Timeout(timeout),
+            None => HcaptchaError::Reqwest(error),
+        }
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl From<Client> for ReqwestTransport {
+    fn from(client: Client) -> ReqwestTransport {
+        ReqwestTransport::with_timeouts(client, None, None)
+    }
+}
+
+#[async_trait]
+impl HcaptchaTransport for ReqwestTransport {
+    async fn post_form(&self, url: &Url, form: &str) -> Result<TransportResponse, HcaptchaError> {
+        let response = self
+            .client
+            .post(url.clone())
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(form.to_owned())
+            .send()
+            .await
+            .map_err(|e| self.reqwest_error(e))?;
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(|v| v.to_owned())
+        };
+        let content_type = header(CONTENT_TYPE);
+        let retry_after = header(RETRY_AFTER);
+        let status = response.status().as_u16();
+        let body = response.bytes().await.map_err(|e| self.reqwest_error(e))?;
+
+        let mut response = TransportResponse::new(status, body.to_vec());
+        if let Some(content_type) = &content_type {
+            response = response.content_type(content_type);
+        }
+        if let Some(retry_after) = &retry_after {
+            response = response.retry_after(retry_after);
+        }
+        Ok(response)
+    }
+}
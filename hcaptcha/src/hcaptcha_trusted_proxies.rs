@@ -0,0 +1,319 @@
+//! Derive the address of the client from the headers set by trusted proxies.
+//!
+//! A request passing through a load balancer or CDN arrives from the
+//! address of the proxy. The proxy records the address it received the
+//! request from in a header, but a client can send the same header with any
+//! value. [TrustedProxies] only reads the header when the request arrived
+//! from a trusted proxy, and then walks the recorded addresses from the
+//! nearest hop back to the first address that is not a trusted proxy.
+//!
+//! # Example
+//! ```
+//!     use hcaptcha::{ProxyHeader, TrustedProxies};
+//!     use std::net::IpAddr;
+//!
+//! # fn main() -> Result<(), hcaptcha::HcaptchaError> {
+//!     let proxies = TrustedProxies::new()
+//!         .trust("10.0.0.0/8")?
+//!         .header(ProxyHeader::XForwardedFor);
+//!
+//!     let peer: IpAddr = "10.0.0.2".parse().unwrap();
+//!     let headers = [("x-forwarded-for", "203.0.113.7, 10.0.0.1")];
+//!
+//!     let remoteip = proxies.remoteip(headers, peer)?;
+//!     assert_eq!(remoteip.to_string(), "203.0.113.7");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::domain::HcaptchaRemoteip;
+use crate::{Code, HcaptchaError};
+use ipnet::IpNet;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// Header in which the trusted proxies record the address of the client.
+///
+/// Deserialized from the lower case name of the header, such as
+/// `x-forwarded-for`.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProxyHeader {
+    /// The `for` parameters of the `Forwarded` header (RFC 7239).
+    Forwarded,
+    /// The `X-Forwarded-For` header.
+    #[default]
+    XForwardedFor,
+    /// The `CF-Connecting-IP` header set by Cloudflare.
+    ///
+    /// The header holds a single address, which is used if the request
+    /// arrived from a trusted proxy.
+    CfConnectingIp,
+}
+
+impl ProxyHeader {
+    /// Name of the header in lower case.
+    fn name(self) -> &'static str {
+        match self {
+            ProxyHeader::Forwarded => "forwarded",
+            ProxyHeader::XForwardedFor => "x-forwarded-for",
+            ProxyHeader::CfConnectingIp => "cf-connecting-ip",
+        }
+    }
+}
+
+/// Networks of the proxies trusted to report the address of the client.
+///
+/// Without trusted networks the address of the connection is always used
+/// and the headers are ignored.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TrustedProxies {
+    /// Networks of the trusted proxies.
+    networks: Vec<IpNet>,
+    /// Header read from the trusted proxies.
+    header: ProxyHeader,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl TrustedProxies {
+    /// Create an empty set of trusted proxies reading the `X-Forwarded-For`
+    /// header.
+    pub fn new() -> TrustedProxies {
+        TrustedProxies::default()
+    }
+
+    /// Trust the proxies in the network.
+    ///
+    /// The network is given in CIDR notation, such as `10.0.0.0/8` or
+    /// `fd00::/8`. A single address is trusted on its own.
+    ///
+    /// # Errors
+    ///
+    /// [HcaptchaError::IpNet] if the network cannot be parsed.
+    pub fn trust(mut self, network: &str) -> Result<Self, HcaptchaError> {
+        let network = match IpAddr::from_str(network) {
+            Ok(ip) => IpNet::from(ip),
+            Err(_) => IpNet::from_str(network)?,
+        };
+        self.networks.push(network);
+        Ok(self)
+    }
+
+    /// Set the header read from the trusted proxies.
+    pub fn header(mut self, header: ProxyHeader) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// Check if the address belongs to a trusted proxy.
+    pub fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|network| network.contains(&ip))
+    }
+
+    /// Get the address of the client from the headers of a request received
+    /// from the peer.
+    ///
+    /// The headers are name and value pairs, with repeated headers read in
+    /// the order given. The address of the peer is returned when the peer
+    /// is not a trusted proxy or the header is absent. Otherwise the
+    /// addresses in the header are read from the last to the first, and the
+    /// first address that is not a trusted proxy is returned. If every
+    /// address is a trusted proxy the first one is returned.
+    ///
+    /// # Errors
+    ///
+    /// An [HcaptchaError::Codes] containing [Code::InvalidUserIp] if an
+    /// address to be read from the header is not a valid ip address, such as
+    /// the `unknown` or obfuscated identifiers of the `Forwarded` header.
+    #[cfg_attr(
+        feature = "trace",
+        tracing::instrument(
+            name = "Get client address from trusted proxy headers.",
+            skip(self, headers),
+            level = "debug"
+        )
+    )]
+    pub fn remoteip<I, K, V>(
+        &self,
+        headers: I,
+        peer: IpAddr,
+    ) -> Result<HcaptchaRemoteip, HcaptchaError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<str>,
+        V: AsRef<[u8]>,
+    {
+        let peer = HcaptchaRemoteip::from(peer);
+        if !self.is_trusted(peer.ip()) {
+            return Ok(peer);
+        }
+
+        let name = self.header.name();
+        let mut hops = Vec::new();
+        for (key, value) in headers {
+            if !key.as_ref().eq_ignore_ascii_case(name) {
+                continue;
+            }
+            let value = std::str::from_utf8(value.as_ref()).map_err(|_| invalid_ip())?;
+            match self.header {
+                ProxyHeader::Forwarded => hops.extend(forwarded_for(value).map(ToOwned::to_owned)),
+                ProxyHeader::XForwardedFor => {
+                    hops.extend(value.split(',').map(|hop| hop.trim().to_owned()))
+                }
+                ProxyHeader::CfConnectingIp => hops = vec![value.trim().to_owned()],
+            }
+        }
+
+        let mut client = peer;
+        for hop in hops.into_iter().rev() {
+            client = HcaptchaRemoteip::parse(hop).map_err(|_| invalid_ip())?;
+            if !self.is_trusted(client.ip()) {
+                break;
+            }
+        }
+
+        #[cfg(feature = "trace")]
+        tracing::debug!("Client address {} for peer {}", client, peer);
+        Ok(client)
+    }
+
+    /// Get the address of the client from the header map of a request
+    /// received from the peer.
+    ///
+    /// See [TrustedProxies::remoteip].
+    #[cfg(feature = "http")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "http")))]
+    pub fn remoteip_from_headers(
+        &self,
+        headers: &http::HeaderMap,
+        peer: IpAddr,
+    ) -> Result<HcaptchaRemoteip, HcaptchaError> {
+        self.remoteip(
+            headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_bytes())),
+            peer,
+        )
+    }
+}
+
+/// Values of the `for` parameters of a `Forwarded` header.
+fn forwarded_for(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').filter_map(|element| {
+        element.split(';').find_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            if key.trim().eq_ignore_ascii_case("for") {
+                Some(value.trim().trim_matches('"'))
+            } else {
+                None
+            }
+        })
+    })
+}
+
+/// Error for an address that is not a valid ip address.
+fn invalid_ip() -> HcaptchaError {
+    let mut codes = HashSet::new();
+    codes.insert(Code::InvalidUserIp);
+    HcaptchaError::Codes(codes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn proxies() -> TrustedProxies {
+        TrustedProxies::new()
+            .trust("10.0.0.0/8")
+            .unwrap()
+            .trust("fd00::/8")
+            .unwrap()
+    }
+
+    fn remoteip(proxies: &TrustedProxies, headers: &[(&str, &str)], peer: &str) -> String {
+        proxies
+            .remoteip(headers.iter().copied(), ip(peer))
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn untrusted_peer_is_the_client() {
+        let headers = [("X-Forwarded-For", "198.51.100.1")];
+        assert_eq!(remoteip(&proxies(), &headers, "203.0.113.7"), "203.0.113.7");
+        assert_eq!(
+            remoteip(&TrustedProxies::new(), &headers, "10.0.0.1"),
+            "10.0.0.1"
+        );
+    }
+
+    #[test]
+    fn trusted_peer_without_header_is_the_client() {
+        assert_eq!(remoteip(&proxies(), &[], "10.0.0.1"), "10.0.0.1");
+    }
+
+    #[test]
+    fn x_forwarded_for_skips_trusted_hops() {
+        let headers = [
+            ("x-forwarded-for", "192.0.2.99, 198.51.100.1"),
+            ("x-forwarded-for", "10.1.1.1"),
+        ];
+        assert_eq!(remoteip(&proxies(), &headers, "10.0.0.1"), "198.51.100.1");
+
+        let headers = [("x-forwarded-for", "10.2.2.2, 10.1.1.1")];
+        assert_eq!(remoteip(&proxies(), &headers, "10.0.0.1"), "10.2.2.2");
+    }
+
+    #[test]
+    fn forwarded_for_parameters_are_read() {
+        let proxies = proxies().header(ProxyHeader::Forwarded);
+        let headers = [(
+            "Forwarded",
+            r#"for=192.0.2.60;proto=http, for="[2001:db8:cafe::17]:4711";by=10.0.0.3, for=10.1.1.1"#,
+        )];
+        assert_eq!(
+            remoteip(&proxies, &headers, "10.0.0.1"),
+            "2001:db8:cafe::17"
+        );
+    }
+
+    #[test]
+    fn cf_connecting_ip_is_read() {
+        let proxies = proxies().header(ProxyHeader::CfConnectingIp);
+        let headers = [
+            ("x-forwarded-for", "192.0.2.99"),
+            ("cf-connecting-ip", "198.51.100.1"),
+        ];
+        assert_eq!(remoteip(&proxies, &headers, "10.0.0.1"), "198.51.100.1");
+    }
+
+    #[test]
+    fn ports_are_dropped() {
+        let headers = [("x-forwarded-for", "198.51.100.1:5000")];
+        assert_eq!(remoteip(&proxies(), &headers, "10.0.0.1"), "198.51.100.1");
+    }
+
+    #[test]
+    fn invalid_hop_is_rejected() {
+        let proxies = proxies().header(ProxyHeader::Forwarded);
+        let headers = [("forwarded", "for=unknown")];
+        match proxies.remoteip(headers, ip("10.0.0.1")) {
+            Err(HcaptchaError::Codes(codes)) => assert!(codes.contains(&Code::InvalidUserIp)),
+            _ => panic!("expected an invalid user ip"),
+        }
+    }
+
+    #[test]
+    fn invalid_network_is_rejected() {
+        assert!(TrustedProxies::new().trust("10.0.0.0/33").is_err());
+        assert!(TrustedProxies::new().trust("10.0.0.1").is_ok());
+    }
+}
@@ -286,32 +286,97 @@
 //! ```
 //!
 //! The following feature flags are available:
-//! * `enterprise` - Enable methods to access enterprise service fields in the  `HcaptchaResponse`
+//! * `enterprise` - Enable methods to access enterprise service fields in the  `HcaptchaResponse` and the `ScorePolicy` to decide a `Verdict` from the score
 //! * `ext` - Enables extended validation of secret
 //! * `trace` - Enables tracing instrumentation on all functions. Traces are logged at the debug level. The value of the secret is not logged.
+//! * `chrono` - Enables methods to access the challenge timestamp of the `HcaptchaResponse` as a UTC date and time
+//! * `blocking` - Enables the [blocking] client and trait to verify responses without an async runtime
+//! * `axum` - Enables the [axum] extractor verifying the token in a form or JSON body before the handler runs
+//! * `actix` - Enables the [actix] extractor and middleware verifying the token with the address of the connection
+//! * `rocket` - Enables the [rocket] data guard and the fairing managing the client and secret from the configuration
+//! * `tower` - Enables the [tower] layer verifying the token in a header, query or body field before forwarding the request
+//! * `reqwest` - Enables the reqwest transport used by [HcaptchaClient::new]
 //! * `nativetls-backend` - Enables native-tls backend in reqwests
 //! * `rustls-backend` - Enables rustls backend in reqwests
 //!
+//! Without the `reqwest` feature reqwest is not a dependency. Provide an
+//! implementation of [HcaptchaTransport] to [HcaptchaClient::with_transport]
+//! to verify responses with another HTTP client.
+//!
 //! # Rust Version
 //!
-//! This version of hcaptcha requires Rust v1.71 or later.
+//! This version of hcaptcha requires Rust v1.75 or later. The web framework
+//! features depend on crates that require a newer Rust:
+//!
+//! * `axum` - Rust v1.78 or later
+//! * `actix` - Rust v1.88 or later
 
+#[cfg(feature = "actix")]
+#[cfg_attr(docsrs, doc(cfg(feature = "actix")))]
+pub mod actix;
+#[cfg(feature = "axum")]
+#[cfg_attr(docsrs, doc(cfg(feature = "axum")))]
+pub mod axum;
+#[cfg(feature = "blocking")]
+#[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+pub mod blocking;
 #[doc(hidden)]
 pub(crate) mod domain;
 mod hcaptcha;
 mod hcaptcha_captcha;
 mod hcaptcha_client;
 mod hcaptcha_error;
+#[cfg(feature = "http")]
+mod hcaptcha_http;
 mod hcaptcha_request;
 mod hcaptcha_response;
+mod hcaptcha_transport;
+mod hcaptcha_trusted_proxies;
+#[cfg(feature = "rocket")]
+#[cfg_attr(docsrs, doc(cfg(feature = "rocket")))]
+pub mod rocket;
+#[cfg(feature = "tower")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+pub mod tower;
 
+pub use domain::HcaptchaRemoteip;
 pub use hcaptcha_captcha::HcaptchaCaptcha;
+pub use hcaptcha_client::CachedSecretProvider;
+pub use hcaptcha_client::CircuitBreaker;
 pub use hcaptcha_client::HcaptchaClient;
+pub use hcaptcha_client::HcaptchaClientBuilder;
+pub use hcaptcha_client::InMemoryReplayGuard;
+pub use hcaptcha_client::OpenPolicy;
+pub use hcaptcha_client::Overflow;
+pub use hcaptcha_client::RateLimit;
+pub use hcaptcha_client::RemoteipPrivacy;
+pub use hcaptcha_client::ReplayGuard;
+pub use hcaptcha_client::RetryPolicy;
+#[cfg(feature = "enterprise")]
+pub use hcaptcha_client::ScorePolicy;
+pub use hcaptcha_client::SecretProvider;
+pub use hcaptcha_client::SecretRegistry;
+pub use hcaptcha_client::Secrets;
+pub use hcaptcha_client::TokenHash;
+#[cfg(feature = "enterprise")]
+pub use hcaptcha_client::Verdict;
+pub use hcaptcha_client::VerificationCache;
+pub use hcaptcha_client::VerificationPolicy;
 pub use hcaptcha_client::VERIFY_URL;
 pub use hcaptcha_error::Code;
 pub use hcaptcha_error::HcaptchaError;
 pub use hcaptcha_request::HcaptchaRequest;
 pub use hcaptcha_response::HcaptchaResponse;
+#[cfg(feature = "enterprise")]
+pub use hcaptcha_response::{Geolocation, UserAgent};
+pub use hcaptcha_transport::HcaptchaTransport;
+pub use hcaptcha_transport::InMemoryTransport;
+#[cfg(feature = "reqwest")]
+pub use hcaptcha_transport::ReqwestTransport;
+pub use hcaptcha_transport::TransportResponse;
+pub use hcaptcha_trusted_proxies::{ProxyHeader, TrustedProxies};
 
 pub use crate::hcaptcha::Hcaptcha;
 pub use hcaptcha_derive::*;
+
+pub use async_trait::async_trait;
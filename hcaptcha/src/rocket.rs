@@ -0,0 +1,515 @@
+//! Rocket data guard and fairing verifying the hcaptcha token.
+//!
+//! The [HcaptchaFairing] reads the `hcaptcha` table of the Rocket
+//! configuration, builds a shared [HcaptchaClient] and manages the
+//! [HcaptchaConfig]. The [VerifiedHcaptcha] data guard deserializes a form
+//! or JSON body into a struct deriving [Hcaptcha] and verifies it with the
+//! managed config.
+//!
+//! The configuration holds the secret, and optionally the url of the API,
+//! the deadline for verification in seconds and the networks of the proxies
+//! trusted to report the address of the client in the proxy header:
+//!
+//! ```toml
+//! [default.hcaptcha]
+//! secret = "0x123456789abcde0f123456789abcdef012345678"
+//! url = "https://api.hcaptcha.com/siteverify"
+//! deadline = 10
+//! trusted_proxies = ["10.0.0.0/8"]
+//! proxy_header = "x-forwarded-for"
+//! ```
+//!
+//! The values can also be set from the environment, for example
+//! `ROCKET_HCAPTCHA={secret="0x123456789abcde0f123456789abcdef012345678"}`.
+//!
+//! # Example
+//! ```no_run
+//!     use hcaptcha::rocket::{HcaptchaFairing, VerifiedHcaptcha};
+//!     use hcaptcha::Hcaptcha;
+//!     use rocket::{post, routes};
+//!
+//!     #[derive(Hcaptcha, serde::Deserialize)]
+//!     struct ContactForm {
+//!         name: String,
+//!         #[captcha]
+//!         #[serde(rename = "h-captcha-response")]
+//!         token: String,
+//!     }
+//!
+//!     #[post("/contact", data = "<form>")]
+//!     fn contact(form: VerifiedHcaptcha<ContactForm>) -> String {
+//!         format!("{}, thank you for your contact request.", form.payload.name)
+//!     }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//!     let _rocket = rocket::build()
+//!         .attach(HcaptchaFairing::new())
+//!         .mount("/", routes![contact])
+//!         .launch()
+//!         .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::domain::HcaptchaSecret;
+use crate::hcaptcha_http::{self, BodyFormat};
+use crate::{Code, Hcaptcha, HcaptchaClient, HcaptchaError, HcaptchaResponse};
+use crate::{ProxyHeader, TrustedProxies};
+use rocket::data::{self, Data, FromData, Limits};
+use rocket::fairing::{self, Fairing, Info, Kind};
+use rocket::http::Status;
+use rocket::outcome::Outcome;
+use rocket::{Build, Request, Rocket};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+/// Status of the response for the error.
+///
+/// Error codes are reported with the most severe status of the codes:
+/// `500 Internal Server Error` for a missing or invalid secret, `400 Bad
+/// Request` for a missing or malformed token, remoteip or sitekey, and `403
+/// Forbidden` when the token was rejected. `503 Service Unavailable` is
+/// reported when the API cannot be reached and `500 Internal Server Error`
+/// for any other error.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "rocket")))]
+pub fn error_status(error: &HcaptchaError) -> Status {
+    Status::new(hcaptcha_http::error_status(error).as_u16())
+}
+
+/// Client, secret and trusted proxies used by the [VerifiedHcaptcha] data
+/// guard.
+///
+/// Managed by the [HcaptchaFairing].
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "rocket")))]
+#[derive(Clone)]
+pub struct HcaptchaConfig {
+    /// Client used to verify the token.
+    client: HcaptchaClient,
+    /// Secret of the site.
+    secret: HcaptchaSecret,
+    /// Proxies trusted to report the address of the client.
+    trusted_proxies: TrustedProxies,
+}
+
+impl fmt::Debug for HcaptchaConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HcaptchaConfig")
+            .field("client", &self.client)
+            .field("trusted_proxies", &self.trusted_proxies)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl HcaptchaConfig {
+    /// Get the client.
+    pub fn client(&self) -> &HcaptchaClient {
+        &self.client
+    }
+
+    /// Get the proxies trusted to report the address of the client.
+    pub fn trusted_proxies(&self) -> &TrustedProxies {
+        &self.trusted_proxies
+    }
+}
+
+/// The `hcaptcha` table of the Rocket configuration.
+#[derive(Deserialize)]
+struct Settings {
+    /// Secret of the site.
+    secret: String,
+    /// Url of the API.
+    url: Option<String>,
+    /// Deadline for verification in seconds.
+    deadline: Option<u64>,
+    /// Networks of the proxies trusted to report the address of the client.
+    #[serde(default)]
+    trusted_proxies: Vec<String>,
+    /// Header read from the trusted proxies.
+    #[serde(default)]
+    proxy_header: ProxyHeader,
+}
+
+/// Fairing building the shared client and managing the [HcaptchaConfig].
+///
+/// Ignition fails if the `hcaptcha` table of the configuration is missing,
+/// has no secret or an invalid secret, has an invalid trusted proxy network,
+/// or the client cannot be built.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "rocket")))]
+#[derive(Debug, Default)]
+pub struct HcaptchaFairing {
+    /// Client to use in place of one built from the configuration.
+    client: Option<HcaptchaClient>,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl HcaptchaFairing {
+    /// Create a fairing building the client from the url and deadline in
+    /// the configuration.
+    pub fn new() -> HcaptchaFairing {
+        HcaptchaFairing::default()
+    }
+
+    /// Create a fairing using the client.
+    ///
+    /// Only the secret and the trusted proxies are read from the
+    /// configuration.
+    pub fn with_client(client: HcaptchaClient) -> HcaptchaFairing {
+        HcaptchaFairing {
+            client: Some(client),
+        }
+    }
+
+    /// Build the config from the settings.
+    fn config(&self, settings: Settings) -> Result<HcaptchaConfig, HcaptchaError> {
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => {
+                let mut builder = HcaptchaClient::builder();
+                if let Some(url) = &settings.url {
+                    builder = builder.url(url);
+                }
+                if let Some(deadline) = settings.deadline {
+                    builder = builder.deadline(Duration::from_secs(deadline));
+                }
+                builder.build()?
+            }
+        };
+        let trusted_proxies = settings
+            .trusted_proxies
+            .iter()
+            .try_fold(TrustedProxies::new(), |proxies, network| {
+                proxies.trust(network)
+            })?
+            .header(settings.proxy_header);
+        Ok(HcaptchaConfig {
+            client,
+            secret: HcaptchaSecret::parse(settings.secret)?,
+            trusted_proxies,
+        })
+    }
+}
+
+#[rocket::async_trait]
+impl Fairing for HcaptchaFairing {
+    fn info(&self) -> Info {
+        Info {
+            name: "hCaptcha",
+            kind: Kind::Ignite,
+        }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        let config = rocket
+            .figment()
+            .extract_inner::<Settings>("hcaptcha")
+            .map_err(|e| e.to_string())
+            .and_then(|settings| self.config(settings).map_err(|e| e.to_string()));
+        match config {
+            Ok(config) => Ok(rocket.manage(config)),
+            Err(_e) => {
+                #[cfg(feature = "trace")]
+                tracing::error!("The hcaptcha fairing failed: {}", _e);
+                Err(rocket)
+            }
+        }
+    }
+}
+
+/// Data guard for a struct deriving [Hcaptcha] whose token has been
+/// verified.
+///
+/// The struct is deserialized from a form
+/// (`application/x-www-form-urlencoded`) or JSON (`application/json`) body,
+/// limited by the `form` or `json` limit of the configuration. A request
+/// with another content type is forwarded. The address of the connection, or
+/// of the client reported by a trusted proxy, is used when the `#[remoteip]`
+/// field is missing or empty.
+///
+/// A failed verification responds with the status from [error_status].
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "rocket")))]
+#[derive(Debug, Clone)]
+pub struct VerifiedHcaptcha<T> {
+    /// The deserialized body.
+    pub payload: T,
+    /// The response from the hcaptcha API.
+    pub response: HcaptchaResponse,
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl<T> VerifiedHcaptcha<T> {
+    /// Get the deserialized body.
+    pub fn into_inner(self) -> T {
+        self.payload
+    }
+}
+
+#[rocket::async_trait]
+impl<'r, T> FromData<'r> for VerifiedHcaptcha<T>
+where
+    T: Hcaptcha + DeserializeOwned + Send + 'static,
+{
+    type Error = HcaptchaError;
+
+    async fn from_data(req: &'r Request<'_>, data: Data<'r>) -> data::Outcome<'r, Self> {
+        let format = match req
+            .content_type()
+            .and_then(|content_type| hcaptcha_http::body_format(&content_type.to_string()))
+        {
+            Some(format) => format,
+            None => return Outcome::Forward((data, Status::UnsupportedMediaType)),
+        };
+        let config = match req.rocket().state::<HcaptchaConfig>() {
+            Some(config) => config,
+            None => {
+                let mut codes = HashSet::new();
+                codes.insert(Code::MissingSecret);
+                let error = HcaptchaError::Codes(codes);
+                return Outcome::Error((error_status(&error), error));
+            }
+        };
+
+        let limit = match format {
+            BodyFormat::Form => req.limits().get("form").unwrap_or(Limits::FORM),
+            BodyFormat::Json => req.limits().get("json").unwrap_or(Limits::JSON),
+        };
+        let body = match data.open(limit).into_bytes().await {
+            Ok(body) if body.is_complete() => body.into_inner(),
+            Ok(_) => {
+                let error = io::Error::new(io::ErrorKind::InvalidData, "body exceeds the limit");
+                return Outcome::Error((Status::PayloadTooLarge, error.into()));
+            }
+            Err(e) => return Outcome::Error((Status::BadRequest, e.into())),
+        };
+        let payload: Result<T, HcaptchaError> = match format {
+            BodyFormat::Form => serde_urlencoded::from_bytes(&body)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into()),
+            BodyFormat::Json => serde_json::from_slice(&body).map_err(HcaptchaError::from),
+        };
+        let payload = match payload {
+            Ok(payload) => payload,
+            Err(e) => return Outcome::Error((Status::UnprocessableEntity, e)),
+        };
+
+        let headers = req
+            .headers()
+            .iter()
+            .map(|header| (header.name().to_string(), header.value().to_owned()));
+        let remoteip = match req.remote() {
+            Some(addr) => match config.trusted_proxies.remoteip(headers, addr.ip()) {
+                Ok(remoteip) => Some(remoteip.to_string()),
+                Err(e) => return Outcome::Error((error_status(&e), e)),
+            },
+            None => None,
+        };
+        match payload
+            .valid_response_with_remoteip(
+                config.secret.expose(),
+                &config.client,
+                remoteip.as_deref(),
+            )
+            .await
+        {
+            Ok(response) => Outcome::Success(VerifiedHcaptcha { payload, response }),
+            Err(e) => Outcome::Error((error_status(&e), e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{HcaptchaCaptcha, HcaptchaRequest, InMemoryTransport, TransportResponse};
+    use rocket::http::ContentType;
+    use rocket::local::asynchronous::Client;
+    use serde_json::json;
+    use std::future::Future;
+    use std::pin::Pin;
+
+    const SECRET: &str = "0x123456789abcde0f123456789abcdef012345678";
+    const TOKEN: &str = "10000000-aaaa-bbbb-cccc-000000000001";
+
+    #[derive(Debug, Deserialize)]
+    struct Contact {
+        name: String,
+        #[serde(rename = "h-captcha-response")]
+        token: String,
+    }
+
+    impl Hcaptcha for Contact {
+        fn valid_response(
+            &self,
+            _secret: &str,
+            _uri: Option<String>,
+        ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>> {
+            unimplemented!("the tests verify with a client")
+        }
+
+        fn valid_response_with_client(
+            &self,
+            secret: &str,
+            client: &HcaptchaClient,
+        ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>> {
+            self.valid_response_with_remoteip(secret, client, None)
+        }
+
+        fn valid_response_with_remoteip(
+            &self,
+            secret: &str,
+            client: &HcaptchaClient,
+            remoteip: Option<&str>,
+        ) -> Pin<Box<dyn Future<Output = Result<HcaptchaResponse, HcaptchaError>> + Send>> {
+            let request = HcaptchaCaptcha::new(&self.token)
+                .and_then(|captcha| match remoteip {
+                    Some(remoteip) => captcha.clone().set_remoteip(remoteip),
+                    None => Ok(captcha),
+                })
+                .and_then(|captcha| HcaptchaRequest::new(secret, captcha));
+            let client = client.clone();
+            Box::pin(async move { client.verify_client_response(request?).await })
+        }
+    }
+
+    #[rocket::post("/", data = "<form>")]
+    fn contact(form: VerifiedHcaptcha<Contact>) -> String {
+        form.into_inner().name
+    }
+
+    async fn rocket_client(body: serde_json::Value) -> (Client, InMemoryTransport) {
+        rocket_client_with(body, rocket::Config::figment()).await
+    }
+
+    async fn rocket_client_with(
+        body: serde_json::Value,
+        figment: rocket::figment::Figment,
+    ) -> (Client, InMemoryTransport) {
+        let transport = InMemoryTransport::new();
+        transport.push_response(TransportResponse::new(200, body.to_string()));
+        let figment = figment.merge(("hcaptcha.secret", SECRET));
+        let rocket = rocket::custom(figment)
+            .attach(HcaptchaFairing::with_client(
+                HcaptchaClient::with_transport(transport.clone()),
+            ))
+            .mount("/", rocket::routes![contact]);
+        (Client::tracked(rocket).await.unwrap(), transport)
+    }
+
+    #[tokio::test]
+    async fn form_is_verified() {
+        let (client, transport) = rocket_client(json!({ "success": true })).await;
+        let response = client
+            .post("/")
+            .header(ContentType::Form)
+            .body(format!("name=Alice&h-captcha-response={}", TOKEN))
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::Ok);
+        assert_eq!(response.into_string().await.unwrap(), "Alice");
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn trusted_proxy_reports_remoteip() {
+        let figment = rocket::Config::figment()
+            .merge(("hcaptcha.trusted_proxies", ["192.168.0.0/16"]))
+            .merge(("hcaptcha.proxy_header", "x-forwarded-for"));
+        let (client, transport) = rocket_client_with(json!({ "success": true }), figment).await;
+        let request = |peer: [u8; 4]| {
+            client
+                .post("/")
+                .remote(std::net::SocketAddr::from((peer, 4000)))
+                .header(ContentType::Form)
+                .header(rocket::http::Header::new("X-Forwarded-For", "203.0.113.7"))
+                .header(rocket::http::Header::new("X-Real-IP", "198.51.100.1"))
+                .body(format!("name=Alice&h-captcha-response={}", TOKEN))
+        };
+
+        let response = request([192, 168, 0, 17]).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(transport.requests()[0].1.contains("remoteip=203.0.113.7"));
+
+        transport.push_response(TransportResponse::new(
+            200,
+            json!({ "success": true }).to_string(),
+        ));
+        let response = request([172, 16, 0, 9]).dispatch().await;
+        assert_eq!(response.status(), Status::Ok);
+        assert!(transport.requests()[1].1.contains("remoteip=172.16.0.9"));
+    }
+
+    async fn status_for_code(code: &str) -> Status {
+        let (client, _) = rocket_client(json!({ "success": false, "error-codes": [code] })).await;
+        let response = client
+            .post("/")
+            .header(ContentType::JSON)
+            .body(json!({ "name": "Bob", "h-captcha-response": TOKEN }).to_string())
+            .dispatch()
+            .await;
+        response.status()
+    }
+
+    #[tokio::test]
+    async fn codes_map_to_status() {
+        assert_eq!(
+            status_for_code("invalid-input-response").await,
+            Status::Forbidden
+        );
+        assert_eq!(status_for_code("bad-request").await, Status::BadRequest);
+        assert_eq!(
+            status_for_code("invalid-input-secret").await,
+            Status::InternalServerError
+        );
+    }
+
+    #[tokio::test]
+    async fn other_content_type_is_forwarded() {
+        let (client, transport) = rocket_client(json!({ "success": true })).await;
+        let response = client
+            .post("/")
+            .header(ContentType::Plain)
+            .body("name=Alice")
+            .dispatch()
+            .await;
+
+        assert_eq!(response.status(), Status::UnsupportedMediaType);
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn ignition_requires_secret() {
+        let rocket = rocket::custom(rocket::Config::figment()).attach(HcaptchaFairing::new());
+        let error = rocket.ignite().await.unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            rocket::error::ErrorKind::FailedFairings(_)
+        ));
+
+        let figment = rocket::Config::figment().merge(("hcaptcha.secret", " "));
+        let rocket = rocket::custom(figment).attach(HcaptchaFairing::new());
+        let error = rocket.ignite().await.unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            rocket::error::ErrorKind::FailedFairings(_)
+        ));
+
+        let figment = rocket::Config::figment()
+            .merge(("hcaptcha.secret", SECRET))
+            .merge(("hcaptcha.trusted_proxies", ["not a network"]));
+        let rocket = rocket::custom(figment).attach(HcaptchaFairing::new());
+        let error = rocket.ignite().await.unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            rocket::error::ErrorKind::FailedFairings(_)
+        ));
+    }
+}
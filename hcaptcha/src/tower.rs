@@ -0,0 +1,498 @@
+//! Tower layer verifying the hcaptcha token before the request is forwarded.
+//!
+//! The [HcaptchaLayer] wraps any service handling [http::Request]s. The
+//! token is read from the configured [TokenSource] and verified with a
+//! shared [HcaptchaClient]. On success the [HcaptchaResponse] is inserted
+//! into the request extensions and the request forwarded to the inner
+//! service. Otherwise an empty response is returned with the status code:
+//!
+//! * `400 Bad Request` - the token is missing or malformed, the address
+//!   reported by a trusted proxy is invalid, or the body cannot be read
+//! * `413 Payload Too Large` - the token is read from a body larger than the
+//!   body limit of the layer
+//! * `415 Unsupported Media Type` - the token is read from a body that is
+//!   neither a form nor JSON
+//! * `403 Forbidden` - the API rejected the token
+//! * `503 Service Unavailable` - the API could not be reached
+//! * `500 Internal Server Error` - any other error, such as an invalid
+//!   secret
+//!
+//! # Example
+//! Protect a service with the token sent in a header.
+//! ```
+//!     use bytes::Bytes;
+//!     use hcaptcha::tower::{HcaptchaBody, HcaptchaLayer, TokenSource};
+//!     use hcaptcha::{HcaptchaClient, HcaptchaResponse};
+//!     use http::{Request, Response};
+//!     use http_body_util::Full;
+//!     use std::convert::Infallible;
+//!     use tower::ServiceBuilder;
+//!
+//!     async fn contact(
+//!         req: Request<HcaptchaBody<Full<Bytes>>>,
+//!     ) -> Result<Response<Full<Bytes>>, Infallible> {
+//!         let response = req.extensions().get::<HcaptchaResponse>();
+//!         let hostname = response.and_then(|r| r.hostname()).unwrap_or_default();
+//!         Ok(Response::new(Full::from(hostname)))
+//!     }
+//!
+//!     let layer = HcaptchaLayer::new(
+//!         HcaptchaClient::new(),
+//!         "0x123456789abcde0f123456789abcdef012345678",
+//!     )
+//!     .token_source(TokenSource::header("x-hcaptcha-token"));
+//!
+//!     let service = ServiceBuilder::new()
+//!         .layer(layer)
+//!         .service_fn(contact);
+//! ```
+//!
+//! # Remoteip
+//!
+//! The address of the connection is sent as the remoteip when the server
+//! inserts the peer [SocketAddr] into the request extensions. Axum's
+//! `ConnectInfo<SocketAddr>` is also read when the `axum` feature is
+//! enabled. The address of the client reported by a proxy is used instead
+//! if the connection is from one of the [TrustedProxies] of the layer.
+
+pub use crate::hcaptcha_http::TokenSource;
+use crate::hcaptcha_http::{self, ConfigSecret};
+use crate::{HcaptchaClient, HcaptchaResponse, TrustedProxies};
+use bytes::Bytes;
+use http::{header, Request, Response, StatusCode};
+use http_body::Body;
+use http_body_util::{BodyExt, Either, Full, LengthLimitError, Limited};
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tower_layer::Layer;
+use tower_service::Service;
+
+/// Default limit on the size of a body read for the token.
+const DEFAULT_BODY_LIMIT: usize = 1024 * 1024;
+
+/// Body of a request forwarded by the [HcaptchaService].
+///
+/// The original body when the token is read from a header or the query, or
+/// the buffered body when the token is read from the body.
+pub type HcaptchaBody<B> = Either<B, Full<Bytes>>;
+
+/// Settings shared by the services of a layer.
+#[derive(Clone)]
+struct Config {
+    /// Client used to verify the token.
+    client: HcaptchaClient,
+    /// Secret of the site.
+    secret: ConfigSecret,
+    /// Location of the token in the request.
+    source: TokenSource,
+    /// Maximum size of a body read for the token.
+    body_limit: usize,
+    /// Proxies trusted to report the address of the client.
+    trusted_proxies: TrustedProxies,
+}
+
+/// Layer verifying the hcaptcha token of each request before forwarding it
+/// to the inner service.
+///
+/// The services created by the layer share the client and its connection
+/// pool.
+///
+/// The request is forwarded with a [HcaptchaBody], so the layer wraps
+/// services over any body with [Bytes] data, such as hyper's `Incoming` or
+/// the bodies of axum and tonic-web.
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+#[derive(Clone)]
+pub struct HcaptchaLayer {
+    config: Config,
+}
+
+impl fmt::Debug for HcaptchaLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HcaptchaLayer")
+            .field("client", &self.config.client)
+            .field("source", &self.config.source)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+impl HcaptchaLayer {
+    /// Create a layer verifying the `h-captcha-response` field of the body
+    /// with the client and secret.
+    pub fn new(client: HcaptchaClient, secret: &str) -> HcaptchaLayer {
+        HcaptchaLayer {
+            config: Config {
+                client,
+                secret: ConfigSecret::new(secret),
+                source: TokenSource::default(),
+                body_limit: DEFAULT_BODY_LIMIT,
+                trusted_proxies: TrustedProxies::new(),
+            },
+        }
+    }
+
+    /// Set the location of the token in the request.
+    pub fn token_source(mut self, source: TokenSource) -> Self {
+        self.config.source = source;
+        self
+    }
+
+    /// Set the maximum size in bytes of a body read for the token.
+    ///
+    /// A larger body is rejected with `413 Payload Too Large`. Defaults to
+    /// 1 MiB.
+    pub fn body_limit(mut self, limit: usize) -> Self {
+        self.config.body_limit = limit;
+        self
+    }
+
+    /// Set the proxies trusted to report the address of the client.
+    ///
+    /// The remoteip of a request arriving from a trusted proxy is read from
+    /// the header of the [TrustedProxies].
+    pub fn trusted_proxies(mut self, trusted_proxies: TrustedProxies) -> Self {
+        self.config.trusted_proxies = trusted_proxies;
+        self
+    }
+}
+
+impl<S> Layer<S> for HcaptchaLayer {
+    type Service = HcaptchaService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        HcaptchaService {
+            inner,
+            config: Arc::new(self.config.clone()),
+        }
+    }
+}
+
+/// Service verifying the hcaptcha token of the request before forwarding it
+/// to the inner service.
+///
+/// Created by the [HcaptchaLayer].
+#[cfg_attr(docsrs, allow(rustdoc::missing_doc_code_examples))]
+#[cfg_attr(docsrs, doc(cfg(feature = "tower")))]
+#[derive(Clone)]
+pub struct HcaptchaService<S> {
+    inner: S,
+    config: Arc<Config>,
+}
+
+impl<S: fmt::Debug> fmt::Debug for HcaptchaService<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HcaptchaService")
+            .field("inner", &self.inner)
+            .field("source", &self.config.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, B, ResBody> Service<Request<B>> for HcaptchaService<S>
+where
+    S: Service<Request<HcaptchaBody<B>>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send,
+    B: Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<Box<dyn Error + Send + Sync>>,
+    ResBody: Default,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        // Use the service that was polled ready and leave the clone in its place.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let config = Arc::clone(&self.config);
+
+        Box::pin(async move {
+            match verify(&config, req).await {
+                Ok(req) => inner.call(req).await,
+                Err(status) => {
+                    let mut response = Response::new(ResBody::default());
+                    *response.status_mut() = status;
+                    Ok(response)
+                }
+            }
+        })
+    }
+}
+
+/// Verify the token of the request and insert the response into its
+/// extensions.
+#[cfg_attr(
+    feature = "trace",
+    tracing::instrument(
+        name = "Verify hcaptcha token before forwarding request.",
+        skip(config, req),
+        level = "debug"
+    )
+)]
+async fn verify<B>(config: &Config, req: Request<B>) -> Result<Request<HcaptchaBody<B>>, StatusCode>
+where
+    B: Body<Data = Bytes>,
+    B::Error: Into<Box<dyn Error + Send + Sync>>,
+{
+    let remoteip = match peer_addr(&req) {
+        Some(addr) => Some(
+            config
+                .trusted_proxies
+                .remoteip_from_headers(req.headers(), addr.ip())
+                .map_err(|e| hcaptcha_http::error_status(&e))?
+                .to_string(),
+        ),
+        None => None,
+    };
+    let (token, mut req) = match &config.source {
+        TokenSource::Header(name) => {
+            let token = req
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(ToOwned::to_owned);
+            (token, req.map(Either::Left))
+        }
+        TokenSource::Query(field) => {
+            let token = hcaptcha_http::query_field(req.uri().query(), field);
+            (token, req.map(Either::Left))
+        }
+        TokenSource::Body(field) => {
+            let format = req
+                .headers()
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(hcaptcha_http::body_format)
+                .ok_or(StatusCode::UNSUPPORTED_MEDIA_TYPE)?;
+            let (parts, body) = req.into_parts();
+            let body = match Limited::new(body, config.body_limit).collect().await {
+                Ok(collected) => collected.to_bytes(),
+                Err(e) if e.is::<LengthLimitError>() => return Err(StatusCode::PAYLOAD_TOO_LARGE),
+                Err(_) => return Err(StatusCode::BAD_REQUEST),
+            };
+            let token = hcaptcha_http::body_field(format, &body, field)
+                .map_err(|_| StatusCode::BAD_REQUEST)?;
+            let body = Either::Right(Full::new(body));
+            (token, Request::from_parts(parts, body))
+        }
+    };
+    let token = token.ok_or(StatusCode::BAD_REQUEST)?;
+
+    let response: HcaptchaResponse =
+        hcaptcha_http::verify_token(&config.client, &config.secret, &token, remoteip.as_deref())
+            .await
+            .map_err(|e| {
+                #[cfg(feature = "trace")]
+                tracing::debug!("Request rejected: {}", e);
+                hcaptcha_http::error_status(&e)
+            })?;
+    req.extensions_mut().insert(response);
+    Ok(req)
+}
+
+/// Address of the connection inserted into the request extensions by the
+/// server.
+fn peer_addr<B>(req: &Request<B>) -> Option<SocketAddr> {
+    let addr = req.extensions().get::<SocketAddr>().copied();
+    #[cfg(feature = "axum")]
+    let addr = addr.or_else(|| {
+        req.extensions()
+            .get::<::axum::extract::ConnectInfo<SocketAddr>>()
+            .map(|info| info.0)
+    });
+    addr
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{InMemoryTransport, TransportResponse};
+    use http_body_util::Full;
+    use serde_json::json;
+    use std::convert::Infallible;
+    use tower::{service_fn, ServiceExt};
+
+    const SECRET: &str = "0x123456789abcde0f123456789abcdef012345678";
+    const TOKEN: &str = "10000000-aaaa-bbbb-cccc-000000000001";
+
+    fn layer(success: bool) -> (HcaptchaLayer, InMemoryTransport) {
+        let transport = InMemoryTransport::new();
+        let body = if success {
+            json!({ "success": true, "hostname": "example.com" })
+        } else {
+            json!({ "success": false, "error-codes": ["invalid-input-response"] })
+        };
+        transport.push_response(TransportResponse::new(200, body.to_string()));
+        let client = HcaptchaClient::with_transport(transport.clone());
+        (HcaptchaLayer::new(client, SECRET), transport)
+    }
+
+    async fn call<B>(layer: HcaptchaLayer, req: Request<B>) -> Response<Full<Bytes>>
+    where
+        B: Body<Data = Bytes> + Send + 'static,
+        B::Error: Into<Box<dyn Error + Send + Sync>>,
+    {
+        let service = layer.layer(service_fn(|req: Request<HcaptchaBody<B>>| async move {
+            let hostname = req
+                .extensions()
+                .get::<HcaptchaResponse>()
+                .and_then(HcaptchaResponse::hostname)
+                .unwrap_or_default();
+            Ok::<_, Infallible>(Response::new(Full::from(hostname)))
+        }));
+        service.oneshot(req).await.unwrap()
+    }
+
+    async fn body_text(response: Response<Full<Bytes>>) -> String {
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn body_token_is_verified_and_body_forwarded() {
+        let (layer, transport) = layer(true);
+        let req = Request::post("/contact")
+            .header(header::CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(Full::from(format!(
+                "name=Alice&h-captcha-response={}",
+                TOKEN
+            )))
+            .unwrap();
+
+        let response = call(layer, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_text(response).await, "example.com");
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn header_token_is_verified() {
+        let (layer, _) = layer(true);
+        let layer = layer.token_source(TokenSource::header("x-hcaptcha-token"));
+        let req = Request::post("/contact")
+            .header("x-hcaptcha-token", TOKEN)
+            .body(Full::from("unread"))
+            .unwrap();
+
+        let response = call(layer, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn query_token_is_verified() {
+        let (layer, _) = layer(true);
+        let layer = layer.token_source(TokenSource::query("token"));
+        let req = Request::get(format!("/contact?token={}", TOKEN))
+            .body(Full::from("unread"))
+            .unwrap();
+
+        let response = call(layer, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn missing_token_is_bad_request() {
+        let (layer, transport) = layer(true);
+        let req = Request::post("/contact")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Full::from(json!({ "name": "Alice" }).to_string()))
+            .unwrap();
+
+        let response = call(layer, req).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn body_over_limit_is_payload_too_large() {
+        let (layer, transport) = layer(true);
+        let layer = layer.body_limit(16);
+        let req = Request::post("/contact")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Full::from(
+                json!({ "h-captcha-response": TOKEN }).to_string(),
+            ))
+            .unwrap();
+
+        let response = call(layer, req).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        assert!(transport.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn header_token_is_verified_with_streamed_body() {
+        let (layer, _) = layer(true);
+        let layer = layer.token_source(TokenSource::header("x-hcaptcha-token"));
+        // A streamed body that cannot be built from bytes, like hyper's `Incoming`.
+        let frame = http_body::Frame::data(Bytes::from("unread"));
+        let frames = futures_util::stream::iter(vec![Ok::<_, Infallible>(frame)]);
+        let body = http_body_util::StreamBody::new(frames);
+        let req = Request::post("/contact")
+            .header("x-hcaptcha-token", TOKEN)
+            .body(body)
+            .unwrap();
+
+        let response = call(layer, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn peer_address_is_sent_as_remoteip() {
+        let (layer, transport) = layer(true);
+        let layer = layer.token_source(TokenSource::header("x-hcaptcha-token"));
+        let mut req = Request::post("/contact")
+            .header("x-hcaptcha-token", TOKEN)
+            .header("x-forwarded-for", "203.0.113.7")
+            .body(Full::from("unread"))
+            .unwrap();
+        req.extensions_mut()
+            .insert(SocketAddr::from(([192, 168, 0, 17], 4000)));
+
+        let response = call(layer, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(transport.requests()[0].1.contains("remoteip=192.168.0.17"));
+    }
+
+    #[tokio::test]
+    async fn trusted_proxy_reports_remoteip() {
+        let (layer, transport) = layer(true);
+        let layer = layer
+            .token_source(TokenSource::header("x-hcaptcha-token"))
+            .trusted_proxies(TrustedProxies::new().trust("192.168.0.0/16").unwrap());
+        let mut req = Request::post("/contact")
+            .header("x-hcaptcha-token", TOKEN)
+            .header("x-forwarded-for", "203.0.113.7")
+            .body(Full::from("unread"))
+            .unwrap();
+        req.extensions_mut()
+            .insert(SocketAddr::from(([192, 168, 0, 17], 4000)));
+
+        let response = call(layer, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(transport.requests()[0].1.contains("remoteip=203.0.113.7"));
+    }
+
+    #[tokio::test]
+    async fn rejected_token_is_forbidden() {
+        let (layer, _) = layer(false);
+        let req = Request::post("/contact")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Full::from(
+                json!({ "h-captcha-response": TOKEN }).to_string(),
+            ))
+            .unwrap();
+
+        let response = call(layer, req).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -27,7 +27,10 @@
 //! }
 //!```
 //!
-//! The derive macro provides code such as the following:
+//! The derive macro provides code such as the following. The
+//! [valid_response](hcaptcha::Hcaptcha::valid_response) method verifies
+//! with a new client. The remoteip supplied by a server framework
+//! integration is used when the `#[remoteip]` field is empty.
 //!
 //!```rust
 //! # use hcaptcha::Hcaptcha;
@@ -41,7 +44,7 @@
 //! #     ip: String,
 //! #     key: String,
 //! # }
-//! impl Hcaptcha for ContactForm {
+//! impl hcaptcha::Hcaptcha for ContactForm {
 //!     fn valid_response(
 //!         &self,
 //!         secret: &str,
@@ -54,15 +57,46 @@
 //!                                     > + Send,
 //!         >,
 //!     > {
-//!         let mut client = hcaptcha::HcaptchaClient::new();
-//!         if let Some(u) = uri {
-//!             match client.set_url(&u) {
-//!                 Ok(c) => client = c,
-//!                 Err(e) => {
-//!                     return Box::pin(async { Err(e) });
-//!                 }
-//!             };
-//!         };
+//!         let mut builder = hcaptcha::HcaptchaClient::builder();
+//!         if let Some(u) = &uri {
+//!             builder = builder.url(u);
+//!         }
+//!         match builder.build() {
+//!             Ok(client) => {
+//!                 hcaptcha::Hcaptcha::valid_response_with_client(self, secret, &client)
+//!             }
+//!             Err(e) => Box::pin(async { Err(e) }),
+//!         }
+//!     }
+//!
+//!     fn valid_response_with_client(
+//!         &self,
+//!         secret: &str,
+//!         client: &hcaptcha::HcaptchaClient,
+//!     ) -> std::pin::Pin<
+//!         Box<
+//!             dyn std::future::Future<
+//!                     Output = Result<hcaptcha::HcaptchaResponse,
+//!                                     hcaptcha::HcaptchaError>,
+//!                                     > + Send,
+//!         >,
+//!     > {
+//!         self.valid_response_with_remoteip(secret, client, None)
+//!     }
+//!
+//!     fn valid_response_with_remoteip(
+//!         &self,
+//!         secret: &str,
+//!         client: &hcaptcha::HcaptchaClient,
+//!         remoteip: Option<&str>,
+//!     ) -> std::pin::Pin<
+//!         Box<
+//!             dyn std::future::Future<
+//!                     Output = Result<hcaptcha::HcaptchaResponse,
+//!                                     hcaptcha::HcaptchaError>,
+//!                                     > + Send,
+//!         >,
+//!     > {
 //!         #[allow(unused_mut)]
 //!         let mut captcha;
 //!         match hcaptcha::HcaptchaCaptcha::new(&self.hcaptcha) {
@@ -71,12 +105,16 @@
 //!                 return Box::pin(async { Err(e) });
 //!             }
 //!         };
-//!         match captcha.set_remoteip(&self.ip) {
-//!             Ok(c) => captcha = c,
-//!             Err(e) => {
-//!                 return Box::pin(async { Err(e) });
-//!             }
-//!         };
+//!         let field: &str = &self.ip;
+//!         let remoteip = if field.is_empty() { remoteip } else { Some(field) };
+//!         if let Some(remoteip) = remoteip {
+//!             match captcha.set_remoteip(remoteip) {
+//!                 Ok(c) => captcha = c,
+//!                 Err(e) => {
+//!                     return Box::pin(async { Err(e) });
+//!                 }
+//!             };
+//!         }
 //!         match captcha.set_sitekey(&self.key) {
 //!             Ok(c) => captcha = c,
 //!             Err(e) => {
@@ -90,7 +128,8 @@
 //!                 return Box::pin(async { Err(e) });
 //!             }
 //!         };
-//!         Box::pin(client.verify_client_response(request))
+//!         let client = client.clone();
+//!         Box::pin(async move { client.verify_client_response(request).await })
 //!     }
 //! }
 //!```
@@ -145,25 +184,37 @@ fn impl_hcaptcha(ast: &DeriveInput) -> TokenStream {
 
     let captcha = get_required_attribute(&attributes, "captcha", name);
 
-    let remoteip = get_optional_attribute(&attributes, "remoteip", "set_remoteip");
+    let remoteip = get_remoteip_attribute(&attributes);
     let sitekey = get_optional_attribute(&attributes, "sitekey", "set_sitekey");
 
     let gen = quote! {
-        impl #impl_generics Hcaptcha for #name #ty_generics #where_clause {
+        impl #impl_generics hcaptcha::Hcaptcha for #name #ty_generics #where_clause {
             fn valid_response(&self, secret: &str, uri: Option<String>) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<hcaptcha::HcaptchaResponse, hcaptcha::HcaptchaError>> + Send>>  {
-                let mut client = hcaptcha::HcaptchaClient::new();
-                if let Some(u) = uri {
-                        match client.set_url(&u)
-                         {
-                            Ok(c) => client = c,
-                            Err(e) => {
-                                return Box::pin(async { Err(e) });
-                            }
-                        };
-                };
+                let mut builder = hcaptcha::HcaptchaClient::builder();
+                if let Some(u) = &uri {
+                    builder = builder.url(u);
+                }
+                match builder.build() {
+                    Ok(client) => hcaptcha::Hcaptcha::valid_response_with_client(self, secret, &client),
+                    Err(e) => Box::pin(async { Err(e) }),
+                }
+            }
+
+            fn valid_response_with_client(&self, secret: &str, client: &hcaptcha::HcaptchaClient) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<hcaptcha::HcaptchaResponse, hcaptcha::HcaptchaError>> + Send>>  {
+                self.valid_response_with_remoteip(secret, client, None)
+            }
 
+            fn valid_response_with_remoteip(&self, secret: &str, client: &hcaptcha::HcaptchaClient, remoteip: Option<&str>) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<hcaptcha::HcaptchaResponse, hcaptcha::HcaptchaError>> + Send>>  {
                 #captcha
                 #remoteip
+                if let Some(remoteip) = remoteip {
+                    match captcha.set_remoteip(remoteip) {
+                        Ok(c) => captcha = c,
+                        Err(e) => {
+                            return Box::pin(async { Err(e) });
+                        }
+                    };
+                }
                 #sitekey;
                 let request;
                 match hcaptcha::HcaptchaRequest::new(&secret, captcha) {
@@ -172,7 +223,8 @@ fn impl_hcaptcha(ast: &DeriveInput) -> TokenStream {
                         return Box::pin(async { Err(e) } );
                     }
                 };
-                Box::pin(client.verify_client_response(request))
+                let client = client.clone();
+                Box::pin(async move { client.verify_client_response(request).await })
             }
         }
     };
@@ -213,6 +265,32 @@ fn get_optional_attribute(
     }
 }
 
+/// Generate tokens for the remoteip attribute
+///
+/// # inputs
+/// - attributes:   Hashmap of attributes
+///
+/// # Output
+///
+/// Token stream preferring the value of the field, if the named attribute is
+/// found and the field is not empty, to the remoteip supplied to the method
+/// Empty token stream if the attribute is not found
+///
+fn get_remoteip_attribute(
+    attributes: &HashMap<String, &proc_macro2::Ident>,
+) -> proc_macro2::TokenStream {
+    match attributes.get("remoteip") {
+        Some(i) => {
+            let i = <&proc_macro2::Ident>::clone(i);
+            quote! {
+                let field: &str = std::convert::AsRef::<str>::as_ref(&self.#i);
+                let remoteip = if field.is_empty() { remoteip } else { Some(field) };
+            }
+        }
+        None => quote! {},
+    }
+}
+
 /// Generate tokens for required attribute
 ///
 /// # inputs
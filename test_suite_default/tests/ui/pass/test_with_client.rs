@@ -0,0 +1,54 @@
+mod helper;
+
+use chrono::{TimeDelta, Utc};
+use claims::assert_ok;
+use hcaptcha::{Hcaptcha, HcaptchaClient};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[derive(Hcaptcha)]
+struct Test {
+    #[captcha]
+    hcaptcha: String,
+}
+
+#[tokio::main]
+async fn main() {
+    // Setup
+    let secret = format!("0x{}", hex::encode(helper::random_string(20)));
+
+    let timestamp = Utc::now()
+        .checked_sub_signed(TimeDelta::try_minutes(10).unwrap())
+        .unwrap()
+        .to_rfc3339();
+
+    let response_template = ResponseTemplate::new(200).set_body_json(json!({
+        "success": true,
+        "challenge_ts": timestamp,
+        "hostname": "test-host",
+    }));
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/siteverify"))
+        .respond_with(response_template)
+        .expect(2)
+        .mount(&mock_server)
+        .await;
+
+    let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+    let client = HcaptchaClient::new_with(&uri).unwrap();
+
+    for _ in 0..2 {
+        let form = Test {
+            hcaptcha: helper::random_string(100),
+        };
+        let response = form.valid_response_with_client(&secret, &client).await;
+
+        assert_ok!(&response);
+        let response = response.unwrap();
+        assert!(&response.success());
+        assert_eq!(&response.timestamp().unwrap(), &timestamp);
+    }
+}
@@ -0,0 +1,57 @@
+mod helper;
+
+use claims::assert_ok;
+use hcaptcha::{Hcaptcha, HcaptchaClient};
+use serde_json::json;
+use wiremock::matchers::{body_string, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[derive(Debug, Hcaptcha)]
+struct Test {
+    #[captcha]
+    hcaptcha: String,
+    #[remoteip]
+    ip: String,
+}
+
+#[tokio::main]
+async fn main() {
+    // Setup
+    let token = helper::random_string(100);
+    let connection_ip = mockd::internet::ipv4_address();
+    let secret = format!("0x{}", hex::encode(helper::random_string(20)));
+
+    let expected_body = format!(
+        "response={}&remoteip={}&secret={}",
+        &token, &connection_ip, &secret
+    );
+
+    let response_template = ResponseTemplate::new(200).set_body_json(json!({
+        "success": true,
+        "hostname": "test-host",
+    }));
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/siteverify"))
+        .and(body_string(&expected_body))
+        .respond_with(response_template)
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let uri = format!("{}{}", mock_server.uri(), "/siteverify");
+    let client = HcaptchaClient::new_with(&uri).unwrap();
+
+    // The empty remoteip field is filled by the address of the connection.
+    let form = Test {
+        hcaptcha: token,
+        ip: String::new(),
+    };
+    let response = form
+        .valid_response_with_remoteip(&secret, &client, Some(&connection_ip))
+        .await;
+
+    assert_ok!(&response);
+    assert!(&response.unwrap().success());
+}
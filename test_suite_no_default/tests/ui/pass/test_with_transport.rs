@@ -0,0 +1,36 @@
+mod helper;
+
+use claims::assert_ok;
+use hcaptcha::{Hcaptcha, HcaptchaClient, InMemoryTransport, TransportResponse};
+use serde_json::json;
+
+#[derive(Hcaptcha)]
+struct Test {
+    #[captcha]
+    hcaptcha: String,
+}
+
+#[tokio::main]
+async fn main() {
+    // Setup
+    let secret = format!("0x{}", hex::encode(helper::random_string(20)));
+
+    let body = json!({
+        "success": true,
+        "hostname": "test-host",
+    });
+    let transport = InMemoryTransport::new();
+    transport.push_response(TransportResponse::new(200, body.to_string()));
+
+    let client = HcaptchaClient::with_transport(transport.clone());
+
+    let form = Test {
+        hcaptcha: helper::random_string(100),
+    };
+    let response = form.valid_response_with_client(&secret, &client).await;
+
+    assert_ok!(&response);
+    let response = response.unwrap();
+    assert!(&response.success());
+    assert_eq!(transport.requests().len(), 1);
+}